@@ -0,0 +1,51 @@
+use rust_url_parser::{levenshtein_distance, BrandGuard};
+use std::time::Instant;
+
+/// Deterministic pseudo-random brand-like domain for index `n`
+fn synthetic_brand(n: u64) -> String {
+    let alphabet = b"abcdefghijklmnopqrstuvwxyz";
+    let mut state = n.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    let len = 5 + (state % 8) as usize;
+    let mut name = String::with_capacity(len + 4);
+    for _ in 0..len {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        name.push(alphabet[(state >> 33) as usize % alphabet.len()] as char);
+    }
+    name.push_str(".com");
+    name
+}
+
+fn main() {
+    let brands: Vec<String> = (0..5_000).map(synthetic_brand).collect();
+    let brand_refs: Vec<&str> = brands.iter().map(String::as_str).collect();
+    let candidates: Vec<String> = (0..200)
+        .map(|n| synthetic_brand(n * 25).replacen('a', "4", 1))
+        .collect();
+    let max_distance = 2;
+
+    let start = Instant::now();
+    let guard = BrandGuard::new(&brand_refs);
+    let build = start.elapsed();
+
+    let start = Instant::now();
+    let mut tree_hits = 0;
+    for candidate in &candidates {
+        tree_hits += guard.nearest(candidate, max_distance).len();
+    }
+    let tree = start.elapsed();
+
+    let start = Instant::now();
+    let mut linear_hits = 0;
+    for candidate in &candidates {
+        linear_hits += brand_refs.iter()
+            .filter(|brand| levenshtein_distance(candidate, brand) <= max_distance)
+            .count();
+    }
+    let linear = start.elapsed();
+
+    println!("brands: {}, lookups: {}, max distance: {}", guard.len(), candidates.len(), max_distance);
+    println!("BK-tree build:  {:?}", build);
+    println!("BK-tree lookup: {:?} ({} hits)", tree, tree_hits);
+    println!("linear scan:    {:?} ({} hits)", linear, linear_hits);
+    println!("speedup:        {:.1}x", linear.as_secs_f64() / tree.as_secs_f64());
+}
@@ -0,0 +1,37 @@
+use rust_url_parser::{ComprehensiveUrlAnalyzer, OutputFormatter, TldAnalyzer, UrlAnalysis, UrlAnalyzer, WhoisFormatter};
+use std::time::Instant;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let analyzer = ComprehensiveUrlAnalyzer::new().with_max_embedded_depth(0);
+    let analyses: Vec<UrlAnalysis> = (0..3_000)
+        .map(|i| analyzer.analyze(&format!(
+            "https://redirect{}.example.com/api/proxy/github.com/user?fallback=https://twitter.com&backup=linkedin.com&site=shop{}.co.uk",
+            i, i % 50,
+        )))
+        .collect::<Result<_, _>>()?;
+    let extractions: usize = analyses.iter()
+        .map(|analysis| analysis.url_components.query_params.len() + analysis.url_components.path_segments.len())
+        .sum();
+
+    // What the formatter used to do: build an analyzer for every extraction
+    let start = Instant::now();
+    for analysis in &analyses {
+        for (_, value) in &analysis.url_components.query_params {
+            let host = url::Url::parse(value).ok().and_then(|url| url.host_str().map(str::to_string));
+            TldAnalyzer::new().extract(host.as_deref().unwrap_or(value))?;
+        }
+    }
+    let per_call = start.elapsed();
+
+    let formatter = WhoisFormatter::new();
+    let start = Instant::now();
+    let output = formatter.format(&analyses)?;
+    let shared = start.elapsed();
+
+    println!("analyses: {}, candidate values: {}, domains: {}", analyses.len(), extractions, output.lines().count());
+    println!("analyzer per extraction (query values only): {:?}", per_call);
+    println!("WhoisFormatter with shared analyzer:         {:?}", shared);
+    println!("speedup:                                     {:.1}x", per_call.as_secs_f64() / shared.as_secs_f64());
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use url::Url;
-use tldextract::{TldExtractor, TldOption};
+use tldextract::TldExtractor;
+pub use tldextract::TldOption;
 use serde::{Serialize, Deserialize};
 
 // ===== TRAITS =====
@@ -19,316 +20,5169 @@ pub trait OutputFormatter<T> {
     fn format(&self, data: &T) -> Result<String, Self::Error>;
 }
 
+/// Trait for looking up a domain's reputation against an external service.
+///
+/// The crate ships no provider; implement this against your own service, usually with a
+/// plain `async fn score`. The lookup future must be `Send` so analyses can be spawned on a
+/// multi-threaded runtime. Providers are taken generically rather than as `dyn`.
+#[cfg(feature = "async")]
+pub trait DomainReputation {
+    fn score(&self, domain: &str) -> impl std::future::Future<Output = ReputationResult> + Send;
+}
+
+// ===== ERRORS =====
+
+/// Machine-readable reason an analysis failed
+#[derive(Debug, Clone, PartialEq)]
+pub enum UrlAnalysisError {
+    /// The input has no `scheme:` and no base to resolve it against
+    MissingScheme,
+    /// None of the base URLs offered for a relative reference is absolute and able to act
+    /// as a base (see `UrlParser::resolve_with_bases`)
+    NoUsableBase,
+    /// The host is empty where the scheme requires one
+    EmptyHost,
+    /// The host contains invalid characters or is a malformed IP literal
+    InvalidHost(String),
+    /// The port could not be represented (see `PortIssue`)
+    MalformedPort(PortIssue),
+    /// The scheme is not one the analyzer accepts
+    /// (see `ComprehensiveUrlAnalyzer::with_allowed_schemes`)
+    UnsupportedScheme(String),
+    /// Any other URL syntax error
+    Parse(url::ParseError),
+    /// Public suffix extraction failed
+    TldExtraction(String),
+    /// The subdomain has more labels than the analyzer allows
+    /// (see `ComprehensiveUrlAnalyzer::with_max_subdomain_labels`)
+    TooManySubdomainLabels { count: usize, max: usize },
+    /// `UrlAnalysis::reanalyze_embedded` was asked for an embedded URL that does not exist
+    EmbeddedIndexOutOfRange { index: usize, len: usize },
+    /// The input has no column with this name (see `analyze_csv_column`)
+    MissingColumn(String),
+    /// A row has no value in the column being analyzed (see `analyze_csv_column`)
+    MissingValue { row: usize, column: String },
+    /// Reading the input (a file, stream or history database) failed
+    Io(String),
+}
+
+impl From<url::ParseError> for UrlAnalysisError {
+    fn from(error: url::ParseError) -> Self {
+        match error {
+            url::ParseError::RelativeUrlWithoutBase => UrlAnalysisError::MissingScheme,
+            url::ParseError::EmptyHost => UrlAnalysisError::EmptyHost,
+            url::ParseError::IdnaError
+            | url::ParseError::InvalidIpv4Address
+            | url::ParseError::InvalidIpv6Address
+            | url::ParseError::InvalidDomainCharacter => UrlAnalysisError::InvalidHost(error.to_string()),
+            other => UrlAnalysisError::Parse(other),
+        }
+    }
+}
+
+impl std::fmt::Display for UrlAnalysisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UrlAnalysisError::MissingScheme => write!(f, "missing scheme"),
+            UrlAnalysisError::NoUsableBase => write!(f, "no usable base URL"),
+            UrlAnalysisError::EmptyHost => write!(f, "empty host"),
+            UrlAnalysisError::InvalidHost(reason) => write!(f, "invalid host: {}", reason),
+            UrlAnalysisError::MalformedPort(issue) => write!(f, "malformed port: {}", issue),
+            UrlAnalysisError::UnsupportedScheme(scheme) => write!(f, "unsupported scheme: {}", scheme),
+            UrlAnalysisError::Parse(error) => write!(f, "parse error: {}", error),
+            UrlAnalysisError::TldExtraction(reason) => write!(f, "TLD extraction failed: {}", reason),
+            UrlAnalysisError::TooManySubdomainLabels { count, max } => {
+                write!(f, "subdomain has {} labels (limit {})", count, max)
+            }
+            UrlAnalysisError::EmbeddedIndexOutOfRange { index, len } => {
+                write!(f, "embedded URL index {} out of range ({} embedded)", index, len)
+            }
+            UrlAnalysisError::MissingColumn(column) => write!(f, "no column named {:?}", column),
+            UrlAnalysisError::MissingValue { row, column } => {
+                write!(f, "row {} has no value in column {:?}", row, column)
+            }
+            UrlAnalysisError::Io(reason) => write!(f, "read error: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for UrlAnalysisError {}
+
 // ===== DATA STRUCTURES =====
 
+/// v1 predates `TldStatus`; every v1 extraction came from the public suffix list, so a
+/// present suffix means `Public`. Applies to nested analyses too.
+fn migrate_v1_to_v2(value: &mut serde_json::Value) {
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+
+    if let Some(tld) = object.get_mut("tld_components").and_then(|tld| tld.as_object_mut()) {
+        if !tld.contains_key("status") {
+            let has_suffix = tld.get("suffix").is_some_and(|suffix| suffix.is_string());
+            let status = if has_suffix { "Public" } else { "Unknown" };
+            tld.insert("status".to_string(), serde_json::Value::from(status));
+        }
+    }
+    if let Some(serde_json::Value::Array(nested)) = object.get_mut("embedded_urls") {
+        nested.iter_mut().for_each(migrate_v1_to_v2);
+    }
+    object.insert("schema_version".to_string(), serde_json::Value::from(2));
+}
+
+/// v2 predates `host_type` and `host_unicode`; both are derived from the stored host, which
+/// is already in its final (lowercased, punycode) form. Applies to nested analyses too.
+fn migrate_v2_to_v3(value: &mut serde_json::Value) {
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+
+    if let Some(url) = object.get_mut("url_components").and_then(|url| url.as_object_mut()) {
+        let host = url.get("host").and_then(|host| host.as_str()).map(str::to_string);
+        let host_type = HostType::of(host.as_deref());
+        if !url.contains_key("host_type") {
+            url.insert("host_type".to_string(), serde_json::to_value(host_type).unwrap_or_default());
+        }
+        if let (Some(host), HostType::Domain, false) = (&host, host_type, url.contains_key("host_unicode")) {
+            url.insert("host_unicode".to_string(), serde_json::Value::from(host_to_unicode(host)));
+        }
+    }
+    if let Some(serde_json::Value::Array(nested)) = object.get_mut("embedded_urls") {
+        nested.iter_mut().for_each(migrate_v2_to_v3);
+    }
+    object.insert("schema_version".to_string(), serde_json::Value::from(3));
+}
+
+/// Version of the serialized `UrlAnalysis` layout written by this crate.
+///
+/// - 1: the original layout, without `schema_version` or `tld_components.status`
+/// - 2: adds `schema_version` and `tld_components.status`
+/// - 3: adds `url_components.host_type` and `url_components.host_unicode`
+pub const SCHEMA_VERSION: u32 = 3;
+
+fn legacy_schema_version() -> u32 {
+    1
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UrlAnalysis {
+    /// Layout version this analysis was written with (see `SCHEMA_VERSION`)
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub original_url: String,
     pub url_components: UrlComponents,
     pub tld_components: TldComponents,
+    /// Hosts discovered inside structured parameter payloads (opt-in)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embedded_hosts: Vec<String>,
+    /// Analyses of URLs nested inside this one's query parameters and path segments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub embedded_urls: Vec<UrlAnalysis>,
+    /// For an embedded URL, the query parameter it was found in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_param: Option<String>,
+    /// For an embedded URL, the index of the path segment it was found in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_segment: Option<usize>,
+    /// Set when a nested URL points back at an endpoint already on the chain above it
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub cycle_detected: bool,
+}
+
+impl UrlAnalysis {
+    /// Deserializes an archived analysis written with layout `version`, migrating it forward
+    /// to `SCHEMA_VERSION` first. Versions newer than this crate understands are rejected.
+    pub fn from_serialized(mut value: serde_json::Value, version: u32) -> Result<Self, serde_json::Error> {
+        if version > SCHEMA_VERSION || version == 0 {
+            return Err(serde::de::Error::custom(format!("unsupported schema version {}", version)));
+        }
+        if version < 2 {
+            migrate_v1_to_v2(&mut value);
+        }
+        if version < 3 {
+            migrate_v2_to_v3(&mut value);
+        }
+        serde_json::from_value(value)
+    }
+
+    /// Returns a 64-bit cache key for the URL. Equivalent URLs (differing only in scheme/host
+    /// case, percent-encoding case, default ports, query parameter order or fragment) produce
+    /// the same key.
+    ///
+    /// The key is FNV-1a over the canonical form, so it is stable across platforms, processes
+    /// and crate versions; any change to the canonical form is treated as a breaking change.
+    pub fn cache_key(&self) -> u64 {
+        fnv1a_64(&self.cache_key_input(true))
+    }
+
+    /// Key identifying the endpoint (scheme, userinfo, host, port and path) while ignoring the
+    /// query, so a redirect chain that loops back through the same endpoint repeats this key
+    fn endpoint_key(&self) -> u64 {
+        fnv1a_64(&self.cache_key_input(false))
+    }
+
+    /// Returns the broadest domain a page at this URL may scope cookies to: the registrable
+    /// domain. IP hosts and hosts without a known suffix have no scope beyond themselves.
+    pub fn cookie_domain_scope(&self) -> Option<String> {
+        self.tld_components.registrable_domain()
+    }
+
+    /// Returns true when a page at this URL may set a cookie with `Domain=domain`: the
+    /// domain must be the host or a parent of it, and never a public suffix such as `co.uk`
+    pub fn can_set_cookie_for(&self, domain: &str) -> bool {
+        let domain = domain.strip_prefix('.').unwrap_or(domain).to_ascii_lowercase();
+        let Some(host) = &self.url_components.host else {
+            return false;
+        };
+        if domain.is_empty() {
+            return false;
+        }
+        if *host == domain {
+            return true;
+        }
+        if is_ip_host(host) || !host.ends_with(&format!(".{}", domain)) {
+            return false;
+        }
+
+        // A parent domain is only allowed when it is at or below the registrable domain
+        self.cookie_domain_scope()
+            .is_some_and(|scope| domain == scope || domain.ends_with(&format!(".{}", scope)))
+    }
+
+    /// Summarizes whether the URL stays within `trusted` registrable domains: its own host
+    /// first, then every domain embedded in query values and path segments, resolved with
+    /// `tld_analyzer` so a batch shares one suffix list
+    pub fn trust_distance(&self, trusted: &std::collections::HashSet<String>, tld_analyzer: &TldAnalyzer) -> TrustDistance {
+        let primary = self.tld_components.registrable_domain()
+            .or_else(|| self.url_components.host.clone());
+        if !primary.is_some_and(|domain| trusted.contains(&domain)) {
+            return TrustDistance::Untrusted;
+        }
+
+        let untrusted: Vec<String> = tld_analyzer.registrable_domains_with_sources(self)
+            .into_iter()
+            .filter(|(domain, sources)| {
+                !trusted.contains(domain) && sources.iter().any(|source| *source != EmbeddedSource::Host)
+            })
+            .map(|(domain, _)| domain)
+            .collect();
+
+        if untrusted.is_empty() {
+            TrustDistance::WithinTrust
+        } else {
+            TrustDistance::RedirectsOffTrust(untrusted)
+        }
+    }
+
+    /// For a redirector analyzed with `with_max_embedded_depth`, returns the first embedded
+    /// URL whose registrable domain differs from this URL's: the real destination a link
+    /// unfurler should fetch. Same-site embedded URLs (e.g. a login return path) are skipped.
+    pub fn primary_external_target(&self) -> Option<String> {
+        let site = |analysis: &UrlAnalysis| {
+            analysis.tld_components.registrable_domain().or_else(|| analysis.url_components.host.clone())
+        };
+        let primary = site(self);
+
+        self.embedded_urls.iter()
+            .find(|embedded| site(embedded) != primary)
+            .map(|embedded| embedded.original_url.clone())
+    }
+
+    /// Guesses what the URL serves, for routing downloads and sandboxes, using the default
+    /// extension mapping
+    pub fn guess_content_class(&self) -> ContentClass {
+        self.guess_content_class_with(&ContentClassMap::default())
+    }
+
+    /// Like `guess_content_class`, with a caller-supplied extension mapping. A known file
+    /// extension wins; otherwise `/api/` or `/graphql` paths and `ws`/`wss` are `Api`, and
+    /// extensionless web paths are `WebPage`.
+    pub fn guess_content_class_with(&self, map: &ContentClassMap) -> ContentClass {
+        let url = &self.url_components;
+        let extension = url.file_extension();
+
+        if let Some(class) = extension.as_deref().and_then(|ext| map.get(ext)) {
+            return class;
+        }
+        let api_path = url.path_segments.iter()
+            .any(|segment| segment.eq_ignore_ascii_case("api") || segment.eq_ignore_ascii_case("graphql"));
+        if api_path || matches!(url.scheme.as_str(), "ws" | "wss") {
+            return ContentClass::Api;
+        }
+        if extension.is_none() && matches!(url.scheme.as_str(), "http" | "https") {
+            return ContentClass::WebPage;
+        }
+
+        ContentClass::Unknown
+    }
+
+    /// Runs a fresh top-level analysis of `embedded_urls[index]` with `analyzer`, as if it had
+    /// been the input, to pivot into a redirect target. Errors if `index` is out of range.
+    pub fn reanalyze_embedded(&self, index: usize, analyzer: &ComprehensiveUrlAnalyzer) -> Result<UrlAnalysis, UrlAnalysisError> {
+        let embedded = self.embedded_urls.get(index).ok_or(UrlAnalysisError::EmbeddedIndexOutOfRange {
+            index,
+            len: self.embedded_urls.len(),
+        })?;
+        analyzer.analyze(&embedded.original_url)
+    }
+
+    /// Returns true for Google AMP cache or viewer URLs (see `amp_canonical_url`)
+    pub fn is_amp(&self) -> bool {
+        self.amp_canonical_url().is_some()
+    }
+
+    /// Recovers the publisher URL behind a Google AMP URL, for unfurling and deduplicating
+    /// AMP against canonical links. Handles the AMP cache
+    /// (`https://www-example-com.cdn.ampproject.org/c/s/www.example.com/article`, where `/s/`
+    /// marks https and the subdomain encodes the host with `-` for `.` and `--` for `-`)
+    /// and the viewer (`https://www.google.com/amp/s/www.example.com/article`).
+    pub fn amp_canonical_url(&self) -> Option<String> {
+        let url = &self.url_components;
+        let host = url.host.as_deref()?;
+        let path = url.path.strip_prefix('/')?;
+
+        let rest = if let Some(encoded_host) = host.strip_suffix(".cdn.ampproject.org") {
+            // `c` is a document, `v` the viewer, `i` an image and `r` a resource
+            match path.split_once('/') {
+                Some(("c" | "v" | "i" | "r", rest)) if !rest.is_empty() => rest,
+                _ => return Some(format!("https://{}/", decode_amp_subdomain(encoded_host))),
+            }
+        } else if self.is_google_host() {
+            path.strip_prefix("amp/")?
+        } else {
+            return None;
+        };
+
+        let (scheme, target) = match rest.strip_prefix("s/") {
+            Some(target) => ("https", target),
+            None => ("http", rest),
+        };
+        let target_host = target.split('/').next().unwrap_or_default();
+        if !target_host.contains('.') {
+            return None;
+        }
+
+        let mut canonical = format!("{}://{}", scheme, target);
+        if !target.contains('/') {
+            canonical.push('/');
+        }
+        if let Some(query) = &url.query {
+            canonical.push('?');
+            canonical.push_str(query);
+        }
+        Some(canonical)
+    }
+
+    /// Whether the host is Google's own search domain (`google.com`, `www.google.co.uk`, ...),
+    /// judged by registrable domain so `google.attacker.net` does not qualify
+    fn is_google_host(&self) -> bool {
+        let tld = &self.tld_components;
+        tld.domain.as_deref() == Some("google")
+            && tld.suffix.as_deref().is_some_and(|suffix| GOOGLE_SUFFIXES.contains(&suffix))
+            && matches!(tld.subdomain.as_deref(), None | Some("www"))
+    }
+
+    /// Detects SSRF aimed at a cloud instance metadata service, checking the host, then
+    /// every embedded URL, IP and domain in order, then each nested embedded URL's own
+    /// targets. Numeric IP obfuscation is already decoded.
+    pub fn targets_cloud_metadata(&self) -> Option<CloudProvider> {
+        let embedded = self.discoveries_ordered()
+            .into_iter()
+            .filter_map(|discovery| match discovery.kind {
+                DiscoveryKind::Url => Url::parse(&discovery.value).ok()?.host_str().map(|host| host.to_string()),
+                DiscoveryKind::Host | DiscoveryKind::Domain | DiscoveryKind::Ip => Some(discovery.value),
+                DiscoveryKind::Email => None,
+            });
+
+        self.url_components.host.iter().cloned()
+            .chain(embedded)
+            .chain(self.embedded_hosts.iter().cloned())
+            .find_map(|host| CloudProvider::from_metadata_host(&host))
+            .or_else(|| self.embedded_urls.iter().find_map(UrlAnalysis::targets_cloud_metadata))
+    }
+
+    /// Hashes the URL's structure for collapsing noisy request logs: the canonical form with
+    /// `volatile_keys` (cache-busters, nonces) dropped from the query and ID-like path segments
+    /// templated as in `path_template`. Keys match exactly.
+    pub fn structural_fingerprint(&self, volatile_keys: &[&str]) -> u64 {
+        let params: Vec<(String, String)> = self.url_components.query_params.iter()
+            .filter(|(key, _)| !volatile_keys.contains(&key.as_str()))
+            .cloned()
+            .collect();
+        fnv1a_64(&self.canonical_url(&self.path_template(), &params))
+    }
+
+    /// Returns true when both URLs have the same canonical form (see `normalized_url`)
+    pub fn loose_eq(&self, other: &UrlAnalysis) -> bool {
+        self.normalized_url() == other.normalized_url()
+    }
+
+    /// Returns true when both URLs are on the same site in the "schemeful same-site" sense:
+    /// the same scheme and the same registrable domain, or the same host for IPs and other
+    /// hosts without one. `https://a.example.com` and `https://b.example.com` are same-site;
+    /// `http://example.com` and `https://example.com` are not. URLs without a host never are.
+    pub fn same_site(&self, other: &UrlAnalysis) -> bool {
+        let site = |analysis: &UrlAnalysis| {
+            let host = analysis.tld_components.registrable_domain()
+                .or_else(|| analysis.url_components.host.clone())
+                .filter(|host| !host.is_empty())?;
+            Some((analysis.url_components.scheme.to_ascii_lowercase(), normalize_percent_case(&host.to_ascii_lowercase())))
+        };
+
+        site(self).is_some_and(|own| Some(own) == site(other))
+    }
+
+    /// Groups URLs by site section: the registrable domain (or host when there is none)
+    /// followed by up to `depth` leading path segments, e.g. `example.com/blog`
+    pub fn site_section_key(&self, depth: usize) -> String {
+        let site = self.tld_components.registrable_domain()
+            .or_else(|| self.url_components.host.clone())
+            .unwrap_or_default();
+
+        let key = std::iter::once(site.as_str())
+            .chain(self.url_components.path_segments.iter().take(depth).map(|s| s.as_str()))
+            .collect::<Vec<_>>()
+            .join("/");
+        normalize_percent_case(&key)
+    }
+
+    /// Replaces ID-like path segments with placeholders so requests to the same endpoint
+    /// group together: `/users/123/orders/456` becomes `/users/{id}/orders/{id}`
+    pub fn path_template(&self) -> String {
+        self.path_template_with(&TemplatePlaceholders::default())
+    }
+
+    /// Like `path_template`, with caller-chosen placeholder text
+    pub fn path_template_with(&self, placeholders: &TemplatePlaceholders) -> String {
+        let segments: Vec<&str> = self.url_components.path_segments.iter()
+            .map(|segment| match IdKind::classify(segment) {
+                Some(IdKind::Numeric) => placeholders.numeric.as_str(),
+                Some(IdKind::Uuid) => placeholders.uuid.as_str(),
+                Some(IdKind::Hex) => placeholders.hex.as_str(),
+                None => segment.as_str(),
+            })
+            .collect();
+
+        format!("/{}", segments.join("/"))
+    }
+
+    /// Finds invisible, bidirectional-override and control characters anywhere in the URL.
+    /// Components are percent-decoded first so encoded tricks (`%E2%80%AE`) are caught too.
+    pub fn suspicious_unicode_report(&self) -> Vec<SuspiciousUnicode> {
+        let url = &self.url_components;
+        let decode = |text: &str| percent_encoding::percent_decode_str(text).decode_utf8_lossy().into_owned();
+
+        let mut parts = vec![
+            ("userinfo", decode(&url.username)),
+            ("host", url.host.clone().unwrap_or_default()),
+            ("path", decode(&url.path)),
+        ];
+        if let Some(query) = &url.query {
+            parts.push(("query", decode(query)));
+        }
+        if let Some(fragment) = &url.fragment {
+            parts.push(("fragment", decode(fragment)));
+        }
+
+        parts.iter()
+            .flat_map(|(component, text)| {
+                text.chars().enumerate().filter_map(move |(index, character)| {
+                    UnicodeIssue::classify(character).map(|issue| SuspiciousUnicode {
+                        codepoint: character as u32,
+                        issue,
+                        component: component.to_string(),
+                        index,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Lists every host, domain, URL, IP and email found, in traversal order: the host
+    /// (then its registrable domain), query values left to right, path segments, then the
+    /// fragment. Nothing is deduplicated; see `unique_discoveries`.
+    pub fn discoveries_ordered(&self) -> Vec<Discovery> {
+        let url = &self.url_components;
+        let mut discoveries = Vec::new();
+        let mut push = |value: String, kind: DiscoveryKind, source: EmbeddedSource| {
+            discoveries.push(Discovery { value, kind, source });
+        };
+
+        if let Some(host) = &url.host {
+            if is_ip_host(host) {
+                push(host.clone(), DiscoveryKind::Ip, EmbeddedSource::Host);
+            } else {
+                push(host.clone(), DiscoveryKind::Host, EmbeddedSource::Host);
+                if let Some(domain) = self.tld_components.registrable_domain() {
+                    push(domain, DiscoveryKind::Domain, EmbeddedSource::Host);
+                }
+            }
+        }
+
+        for (key, value) in &url.query_params {
+            if let Some(kind) = classify_discovery(value) {
+                push(value.clone(), kind, EmbeddedSource::QueryParam(key.clone()));
+            }
+        }
+
+        for (index, segment) in url.path_segments.iter().enumerate() {
+            let decoded = percent_encoding::percent_decode_str(segment).decode_utf8_lossy();
+            if let Some(kind) = classify_discovery(&decoded) {
+                push(decoded.into_owned(), kind, EmbeddedSource::PathSegment(index));
+            }
+        }
+
+        if let Some(fragment) = &url.fragment {
+            let decoded = percent_encoding::percent_decode_str(fragment).decode_utf8_lossy();
+            if let Some(kind) = classify_discovery(&decoded) {
+                push(decoded.into_owned(), kind, EmbeddedSource::Fragment);
+            } else if fragment.contains('=') {
+                for (_, value) in url::form_urlencoded::parse(fragment.as_bytes()) {
+                    if let Some(kind) = classify_discovery(&value) {
+                        push(value.into_owned(), kind, EmbeddedSource::Fragment);
+                    }
+                }
+            }
+        }
+
+        discoveries
+    }
+
+    /// Lists the primary host and every host discovered in query values, path segments, the
+    /// fragment (URLs, bare domains, email domains) and embedded URL analyses that end in one
+    /// of `suffixes`, e.g. only `gov` and `mil`. A suffix matches whole trailing labels, not
+    /// just public suffixes, so `census.gov` matches `data.census.gov` and `example.com`
+    /// matches itself. Suffixes match case-insensitively, with or without a leading dot.
+    /// Hosts are lowercased and deduplicated in discovery order.
+    pub fn hosts_with_suffix(&self, suffixes: &std::collections::HashSet<String>) -> Vec<String> {
+        let suffixes: Vec<String> = suffixes.iter()
+            .map(|suffix| suffix.trim_matches('.').to_ascii_lowercase())
+            .collect();
+        let matches = |host: &str| {
+            suffixes.iter().any(|suffix| host == suffix || host.strip_suffix(suffix.as_str()).is_some_and(|rest| rest.ends_with('.')))
+        };
+
+        let mut hosts = Vec::new();
+        self.collect_discovered_hosts(&mut hosts);
+        hosts.into_iter().filter(|host| matches(host)).collect()
+    }
+
+    /// Pushes this analysis's hosts, then those of its embedded URLs, skipping repeats
+    fn collect_discovered_hosts(&self, hosts: &mut Vec<String>) {
+        let mut push = |host: &str| {
+            let host = host.trim_end_matches('.').to_ascii_lowercase();
+            if !host.is_empty() && !is_ip_host(&host) && !hosts.contains(&host) {
+                hosts.push(host);
+            }
+        };
+
+        for discovery in self.discoveries_ordered() {
+            match (discovery.kind, &discovery.source) {
+                (DiscoveryKind::Host, _) => push(&discovery.value),
+                // The host's own registrable domain is not a separate host
+                (DiscoveryKind::Domain, EmbeddedSource::Host) => {}
+                (DiscoveryKind::Domain, _) => push(&discovery.value),
+                (DiscoveryKind::Url, _) => {
+                    if let Some(host) = Url::parse(&discovery.value).ok().and_then(|url| url.host_str().map(str::to_string)) {
+                        push(&host);
+                    }
+                }
+                (DiscoveryKind::Email, _) => {
+                    if let Some((_, domain)) = discovery.value.rsplit_once('@') {
+                        push(domain);
+                    }
+                }
+                (DiscoveryKind::Ip, _) => {}
+            }
+        }
+
+        for embedded in &self.embedded_urls {
+            embedded.collect_discovered_hosts(hosts);
+        }
+    }
+
+    /// `discoveries_ordered` keeping only the first occurrence of each value and kind
+    pub fn unique_discoveries(&self) -> Vec<Discovery> {
+        let mut seen = std::collections::HashSet::new();
+        self.discoveries_ordered()
+            .into_iter()
+            .filter(|discovery| seen.insert((discovery.value.clone(), discovery.kind)))
+            .collect()
+    }
+
+    /// Flags empty components that suggest the URL was cut off or mangled when copied
+    pub fn completeness_issues(&self) -> Vec<CompletenessIssue> {
+        let url = &self.url_components;
+        let mut issues = Vec::new();
+
+        if let Some(query) = url.query.as_deref().filter(|query| !query.is_empty()) {
+            for pair in query.split('&') {
+                if let Some(key) = pair.strip_suffix('=').filter(|key| !key.is_empty() && !key.contains('=')) {
+                    let key = percent_encoding::percent_decode_str(&key.replace('+', " ")).decode_utf8_lossy().into_owned();
+                    issues.push(CompletenessIssue::EmptyQueryValue(key));
+                }
+            }
+        }
+        // The url crate inserts `/` for special schemes, so check what was written
+        let raw = self.original_url.trim();
+        let query_follows_host = raw.split_once("://")
+            .is_some_and(|(_, rest)| rest.find(['/', '\\', '?', '#']).is_some_and(|end| rest[end..].starts_with('?')));
+        if url.query.is_some() && query_follows_host {
+            issues.push(CompletenessIssue::QueryWithoutPath);
+        }
+        if url.query.as_deref() == Some("") {
+            issues.push(CompletenessIssue::DanglingQueryDelimiter);
+        }
+        if url.fragment.as_deref() == Some("") {
+            issues.push(CompletenessIssue::DanglingFragmentDelimiter);
+        }
+
+        issues
+    }
+
+    /// Collects non-fatal red flags about the URL (see `Warning`)
+    pub fn warnings(&self) -> Vec<Warning> {
+        let url = &self.url_components;
+        let mut warnings = Vec::new();
+
+        if !url.username.is_empty() || url.password.is_some() {
+            warnings.push(Warning::CredentialsPresent);
+        }
+        if let Some(port) = url.port {
+            warnings.push(Warning::NonStandardPort(port));
+        }
+        if url.host.as_deref().is_some_and(|host| host.split('.').any(|label| label.starts_with("xn--"))) {
+            warnings.push(Warning::PunycodeHost);
+        }
+        if url.authority_ambiguous {
+            warnings.push(Warning::AmbiguousAuthority);
+        }
+        // The url crate resolves dot segments, so look for them in the raw input
+        let raw = self.original_url.to_ascii_lowercase();
+        if ["../", "..\\", "/..", "%2e%2e", ".%2e", "%2e."].iter().any(|pattern| raw.contains(pattern)) {
+            warnings.push(Warning::PathTraversal);
+        }
+        if url.ip_was_obfuscated {
+            warnings.push(Warning::ObfuscatedIp);
+        }
+
+        warnings
+    }
+
+    /// Builds a simplified, credential-free label for showing the URL in a UI
+    pub fn display_url(&self, opts: DisplayOptions) -> String {
+        let url = &self.url_components;
+        let mut display = String::new();
+
+        if !opts.hide_scheme {
+            display.push_str(&url.scheme);
+            display.push_str("://");
+        }
+        if let Some(host) = &url.host {
+            let host = if opts.strip_www { host.strip_prefix("www.").unwrap_or(host) } else { host };
+            display.push_str(host);
+        }
+        if let Some(port) = url.port {
+            display.push_str(&format!(":{}", port));
+        }
+
+        let mut rest = url.path.trim_end_matches('/').to_string();
+        if let Some(query) = &url.query {
+            rest.push('?');
+            rest.push_str(query);
+        }
+        if let Some(max_len) = opts.max_path_len {
+            if rest.chars().count() > max_len {
+                rest = rest.chars().take(max_len).collect();
+                rest.push('…');
+            }
+        }
+        display.push_str(&rest);
+
+        display
+    }
+
+    /// Returns the URL in canonical form: lowercase scheme and host, uppercase percent-escapes,
+    /// `/` for an empty path, query parameters sorted and the fragment dropped
+    pub fn normalized_url(&self) -> String {
+        self.canonical_url(&self.url_components.path, &self.url_components.query_params)
+    }
+
+    /// Canonical string hashed by `cache_key`
+    fn cache_key_input(&self, include_query: bool) -> String {
+        let params: &[(String, String)] = if include_query { &self.url_components.query_params } else { &[] };
+        self.canonical_url(&self.url_components.path, params)
+    }
+
+    /// Canonical form of the URL with `path` and `params` in place of its own
+    fn canonical_url(&self, path: &str, params: &[(String, String)]) -> String {
+        let url = &self.url_components;
+        // Query params are decoded and re-encoded below, so only the prefix needs this
+        let mut canonical = normalize_percent_case(&format!(
+            "{}{}", url.canonical_origin(), if path.is_empty() { "/" } else { path }
+        ));
+
+        if let Some(query) = sorted_form_query(params) {
+            canonical.push('?');
+            canonical.push_str(&query);
+        }
+
+        canonical
+    }
+}
+
+/// Cloud whose instance metadata service a URL targets (see `UrlAnalysis::targets_cloud_metadata`)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloudProvider {
+    /// `169.254.169.254` / `fd00:ec2::254` (also answered by several other clouds) and the
+    /// ECS task endpoint `169.254.170.2`
+    Aws,
+    /// `metadata.google.internal`
+    Gcp,
+    /// `metadata.azure.com`
+    Azure,
+    /// `100.100.100.200`
+    Alibaba,
+}
+
+impl CloudProvider {
+    /// Maps a well-known metadata hostname or IP to its provider
+    pub fn from_metadata_host(host: &str) -> Option<Self> {
+        let host = host.trim_start_matches('[').trim_end_matches(']').trim_end_matches('.').to_ascii_lowercase();
+        match host.as_str() {
+            "169.254.169.254" | "169.254.170.2" | "fd00:ec2::254" | "instance-data" | "instance-data.ec2.internal" => Some(CloudProvider::Aws),
+            "metadata.google.internal" | "metadata" => Some(CloudProvider::Gcp),
+            "metadata.azure.com" => Some(CloudProvider::Azure),
+            "100.100.100.200" => Some(CloudProvider::Alibaba),
+            _ => None,
+        }
+    }
+}
+
+/// Sign of a truncated or mangled URL, from `UrlAnalysis::completeness_issues`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum CompletenessIssue {
+    /// `key=` with nothing after the `=`
+    EmptyQueryValue(String),
+    /// A query follows the host directly with no path (`https://example.com?x=1`)
+    QueryWithoutPath,
+    /// The URL ends in a bare `?`
+    DanglingQueryDelimiter,
+    /// The URL ends in a bare `#`
+    DanglingFragmentDelimiter,
+}
+
+/// Result of `UrlAnalysis::trust_distance`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum TrustDistance {
+    /// The host and every embedded domain are trusted
+    WithinTrust,
+    /// The host is trusted but these embedded registrable domains (sorted) are not
+    RedirectsOffTrust(Vec<String>),
+    /// The host itself is not trusted
+    Untrusted,
+}
+
+/// Non-fatal issue surfaced by `UrlAnalysis::warnings` / `analyze_with_warnings`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// Username and/or password embedded in the URL
+    CredentialsPresent,
+    /// Explicit port other than the scheme's default
+    NonStandardPort(u16),
+    /// Host contains IDNA (`xn--`) labels
+    PunycodeHost,
+    /// Authority could mislead about the real host (see `UrlComponents::authority_ambiguous`)
+    AmbiguousAuthority,
+    /// Raw input contains `..` traversal sequences (possibly percent-encoded)
+    PathTraversal,
+    /// Host is an IPv4 address written in hex, octal or decimal form
+    ObfuscatedIp,
+}
+
+/// Category of a suspicious character found by `UrlAnalysis::suspicious_unicode_report`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnicodeIssue {
+    /// Bidirectional override/isolate/mark, e.g. U+202E RIGHT-TO-LEFT OVERRIDE
+    BidiControl,
+    /// Zero-width characters such as U+200B ZERO WIDTH SPACE
+    ZeroWidth,
+    /// Other characters that render as nothing (soft hyphen, Hangul filler, ...)
+    Invisible,
+    /// C0/C1 control characters
+    Control,
+}
+
+impl UnicodeIssue {
+    fn classify(character: char) -> Option<Self> {
+        match character {
+            '\u{200E}' | '\u{200F}' | '\u{061C}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => Some(UnicodeIssue::BidiControl),
+            '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' => Some(UnicodeIssue::ZeroWidth),
+            '\u{00AD}' | '\u{034F}' | '\u{115F}' | '\u{1160}' | '\u{180E}' | '\u{3164}' | '\u{FFA0}' => Some(UnicodeIssue::Invisible),
+            c if c.is_control() => Some(UnicodeIssue::Control),
+            _ => None,
+        }
+    }
+}
+
+/// One suspicious character and where it occurs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SuspiciousUnicode {
+    pub codepoint: u32,
+    pub issue: UnicodeIssue,
+    /// `userinfo`, `host`, `path`, `query` or `fragment`
+    pub component: String,
+    /// Character index within the decoded component
+    pub index: usize,
+}
+
+/// Writing system of a host character, as far as `UrlComponents::detect_mixed_script` needs
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UnicodeScript {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Hangul,
+    Hiragana,
+    Katakana,
+    Han,
+    /// Any other letter
+    Other,
+}
+
+impl UnicodeScript {
+    /// Script of a letter; `None` for digits, `-` and other script-neutral characters
+    fn of(character: char) -> Option<Self> {
+        let script = match character as u32 {
+            0x41..=0x5A | 0x61..=0x7A | 0xC0..=0xD6 | 0xD8..=0xF6 | 0xF8..=0x24F | 0x1E00..=0x1EFF => UnicodeScript::Latin,
+            0x370..=0x3FF | 0x1F00..=0x1FFF => UnicodeScript::Greek,
+            0x400..=0x52F | 0x2DE0..=0x2DFF | 0xA640..=0xA69F => UnicodeScript::Cyrillic,
+            0x530..=0x58F => UnicodeScript::Armenian,
+            0x590..=0x5FF => UnicodeScript::Hebrew,
+            0x600..=0x6FF | 0x750..=0x77F => UnicodeScript::Arabic,
+            0x900..=0x97F => UnicodeScript::Devanagari,
+            0xE00..=0xE7F => UnicodeScript::Thai,
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => UnicodeScript::Hangul,
+            0x3040..=0x309F => UnicodeScript::Hiragana,
+            0x30A0..=0x30FF => UnicodeScript::Katakana,
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF => UnicodeScript::Han,
+            _ if character.is_alphabetic() => UnicodeScript::Other,
+            _ => return None,
+        };
+        Some(script)
+    }
+
+    /// Whether a label written in exactly these scripts is normal usage: one script, or
+    /// Latin mixed with Japanese or Korean as registries allow
+    fn is_accepted_mix(scripts: &std::collections::BTreeSet<Self>) -> bool {
+        use UnicodeScript::*;
+        let japanese = [Latin, Han, Hiragana, Katakana];
+        let korean = [Latin, Han, Hangul];
+        scripts.len() <= 1
+            || scripts.iter().all(|script| japanese.contains(script))
+            || scripts.iter().all(|script| korean.contains(script))
+    }
+}
+
+/// ASCII letter that a non-ASCII host character is commonly used to imitate
+fn ascii_confusable(character: char) -> Option<char> {
+    let ascii = match character {
+        // Cyrillic
+        'а' => 'a', 'е' => 'e', 'о' => 'o', 'р' => 'p', 'с' => 'c', 'у' => 'y', 'х' => 'x',
+        'і' => 'i', 'ј' => 'j', 'ѕ' => 's', 'һ' => 'h', 'ԁ' => 'd', 'ԛ' => 'q', 'ԝ' => 'w', 'ӏ' => 'l',
+        // Greek
+        'α' => 'a', 'ο' => 'o', 'ν' => 'v', 'ι' => 'i', 'κ' => 'k', 'ρ' => 'p', 'υ' => 'u', 'χ' => 'x',
+        // Armenian
+        'օ' => 'o', 'ս' => 'u', 'հ' => 'h', 'ո' => 'n',
+        // Latin lookalikes outside ASCII
+        'ı' => 'i', 'ɡ' => 'g', 'ɑ' => 'a',
+        _ => return None,
+    };
+    Some(ascii)
+}
+
+/// Result of `UrlComponents::detect_mixed_script`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MixedScriptReport {
+    /// Every script used by letters in the host, sorted
+    pub scripts: Vec<UnicodeScript>,
+    /// Labels of the Unicode host that mix scripts, in host order
+    pub mixed_labels: Vec<String>,
+    /// Non-ASCII host characters that imitate ASCII letters, in host order
+    pub confusables: Vec<ConfusableChar>,
+}
+
+/// A host character that looks like an ASCII letter
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConfusableChar {
+    pub character: char,
+    pub looks_like: char,
+    pub script: UnicodeScript,
+}
+
+/// Placeholder text used by `UrlAnalysis::path_template_with` for each kind of ID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplatePlaceholders {
+    pub numeric: String,
+    pub uuid: String,
+    pub hex: String,
+}
+
+impl Default for TemplatePlaceholders {
+    fn default() -> Self {
+        Self {
+            numeric: "{id}".to_string(),
+            uuid: "{uuid}".to_string(),
+            hex: "{hash}".to_string(),
+        }
+    }
+}
+
+/// Broad kind of content a URL probably serves (see `UrlAnalysis::guess_content_class`)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentClass {
+    WebPage,
+    Image,
+    Document,
+    Archive,
+    Executable,
+    Api,
+    Media,
+    Unknown,
+}
+
+/// File extension to `ContentClass` mapping used by `UrlAnalysis::guess_content_class_with`
+#[derive(Debug, Clone)]
+pub struct ContentClassMap {
+    extensions: std::collections::HashMap<String, ContentClass>,
+}
+
+impl ContentClassMap {
+    /// Creates a map with the default extensions
+    pub fn new() -> Self {
+        let defaults: &[(ContentClass, &[&str])] = &[
+            (ContentClass::WebPage, &["html", "htm", "xhtml", "php", "asp", "aspx", "jsp"]),
+            (ContentClass::Image, &["jpg", "jpeg", "png", "gif", "webp", "svg", "bmp", "ico", "tif", "tiff", "avif"]),
+            (ContentClass::Document, &["pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "rtf", "txt", "csv"]),
+            (ContentClass::Archive, &["zip", "rar", "7z", "tar", "gz", "tgz", "bz2", "xz", "iso"]),
+            (ContentClass::Executable, &["exe", "msi", "dll", "scr", "bat", "cmd", "ps1", "vbs", "jar", "apk", "dmg", "pkg", "deb", "rpm", "sh", "elf", "bin"]),
+            (ContentClass::Api, &["json", "xml"]),
+            (ContentClass::Media, &["mp3", "mp4", "m4a", "wav", "ogg", "webm", "mov", "avi", "mkv", "flac", "m3u8"]),
+        ];
+
+        Self {
+            extensions: defaults.iter()
+                .flat_map(|(class, extensions)| extensions.iter().map(move |ext| (ext.to_string(), *class)))
+                .collect(),
+        }
+    }
+
+    /// Creates a map with no extensions, for callers supplying their own
+    pub fn empty() -> Self {
+        Self {
+            extensions: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Maps `extension` (without the dot, case-insensitive) to `class`, replacing any default
+    pub fn with_extension(mut self, extension: &str, class: ContentClass) -> Self {
+        self.extensions.insert(extension.trim_start_matches('.').to_ascii_lowercase(), class);
+        self
+    }
+
+    pub fn get(&self, extension: &str) -> Option<ContentClass> {
+        self.extensions.get(&extension.to_ascii_lowercase()).copied()
+    }
+}
+
+impl Default for ContentClassMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Port to service name mapping used by `UrlComponents::well_known_service_with`
+#[derive(Debug, Clone)]
+pub struct ServicePortMap {
+    services: std::collections::HashMap<u16, String>,
+}
+
+impl ServicePortMap {
+    /// Creates a map with the default IANA-registered (or de facto) service ports
+    pub fn new() -> Self {
+        let defaults: &[(u16, &str)] = &[
+            (20, "ftp-data"), (21, "ftp"), (22, "ssh"), (23, "telnet"), (25, "smtp"),
+            (53, "dns"), (80, "http"), (110, "pop3"), (143, "imap"), (389, "ldap"),
+            (443, "https"), (445, "smb"), (465, "smtps"), (587, "submission"), (636, "ldaps"),
+            (993, "imaps"), (995, "pop3s"), (1433, "mssql"), (1521, "oracle"), (2049, "nfs"),
+            (2375, "docker"), (2376, "docker-tls"), (3306, "mysql"), (3389, "rdp"),
+            (5432, "postgresql"), (5672, "amqp"), (5900, "vnc"), (6379, "redis"),
+            (6443, "kubernetes-api"), (8080, "http-alt"), (8443, "https-alt"), (9092, "kafka"),
+            (9200, "elasticsearch"), (11211, "memcached"), (27017, "mongodb"),
+        ];
+
+        Self {
+            services: defaults.iter().map(|(port, name)| (*port, name.to_string())).collect(),
+        }
+    }
+
+    /// Creates a map with no ports, for callers supplying their own
+    pub fn empty() -> Self {
+        Self {
+            services: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Maps `port` to `service`, replacing any default
+    pub fn with_port(mut self, port: u16, service: &str) -> Self {
+        self.services.insert(port, service.to_string());
+        self
+    }
+
+    pub fn get(&self, port: u16) -> Option<&str> {
+        self.services.get(&port).map(String::as_str)
+    }
+}
+
+impl Default for ServicePortMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Toggles for `UrlAnalysis::display_url`. Credentials are always hidden.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisplayOptions {
+    /// Omit the `scheme://` prefix
+    pub hide_scheme: bool,
+    /// Drop a leading `www.` from the host
+    pub strip_www: bool,
+    /// Truncate the path (and query) to this many characters, appending an ellipsis
+    pub max_path_len: Option<usize>,
+}
+
+/// How `read_url_lines` cleans up each line of a URL list
+#[derive(Debug, Clone)]
+pub struct ReaderOptions {
+    /// Lines starting with this (after leading whitespace) are skipped; `None` disables comments
+    pub comment_prefix: Option<String>,
+    /// Drop a comment that follows the URL after whitespace (`https://a.com  # note`).
+    /// A prefix with no whitespace before it, like a `#fragment`, is kept.
+    pub strip_inline_comments: bool,
+    /// Skip lines that are empty after trimming
+    pub skip_blank: bool,
+}
+
+impl Default for ReaderOptions {
+    fn default() -> Self {
+        Self {
+            comment_prefix: Some("#".to_string()),
+            strip_inline_comments: true,
+            skip_blank: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UrlComponents {
+    pub scheme: String,
+    pub username: String,
+    pub password: Option<String>,
+    pub host: Option<String>,
+    /// Whether `host` is a domain name or an IP literal
+    #[serde(default)]
+    pub host_type: HostType,
+    /// `host` with punycode labels decoded (`xn--mnchen-3ya.de` -> `münchen.de`); equal to
+    /// `host` for plain ASCII names and `None` for IP literals or no host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_unicode: Option<String>,
+    pub port: Option<u16>,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+    pub query_params: Vec<(String, String)>,
+    pub path_segments: Vec<String>,
+    /// Fixes applied to the input by lenient parsing, in the order they were made
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repairs: Vec<String>,
+    /// Byte ranges of each component in the original input (opt-in, see `UrlParser::with_component_spans`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component_spans: Option<ComponentSpans>,
+    /// Set when the raw authority could mislead a reader about the real host: more than one
+    /// unencoded `@`, or userinfo that itself looks like a domain (`https://paypal.com@evil.com`)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub authority_ambiguous: bool,
+    /// Suspicious port spelling found by lenient parsing (port `0`, leading zeros)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port_issue: Option<PortIssue>,
+    /// Server/share breakdown for `file://server/share/...` and `\\server\share\...` inputs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unc: Option<UncPath>,
+    /// Set when the raw input had backslashes before the query, which parsers disagree on
+    /// (see `UrlParser::with_backslash_preservation`)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub contained_backslashes: bool,
+    /// Set when the host was written as a hex, octal, decimal or shortened IPv4 address
+    /// (`0x7f000001`, `0177.0.0.1`, `2130706433`); `host` then holds the dotted form
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub ip_was_obfuscated: bool,
+}
+
+/// A Windows UNC path (`\\server\share\dir\file`) broken into its parts
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UncPath {
+    pub server: String,
+    pub share: Option<String>,
+    /// Remainder below the share, `/`-separated and percent-decoded
+    pub path: String,
+}
+
+impl UncPath {
+    /// Converts a raw backslash UNC path into an equivalent `file://` URL
+    fn to_file_url(raw: &str) -> Option<String> {
+        let rest = raw.trim().strip_prefix("\\\\")?;
+        if rest.is_empty() || rest.starts_with('\\') {
+            return None;
+        }
+        Some(format!("file://{}", rest.replace('\\', "/")))
+    }
+
+    fn from_file_url(url: &Url) -> Option<Self> {
+        if url.scheme() != "file" {
+            return None;
+        }
+        let server = url.host_str().filter(|host| !host.is_empty())?.to_ascii_lowercase();
+
+        let mut segments = url.path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty())
+                .map(|s| percent_encoding::percent_decode_str(s).decode_utf8_lossy().into_owned())
+                .collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter();
+
+        Some(UncPath {
+            server,
+            share: segments.next(),
+            path: segments.collect::<Vec<_>>().join("/"),
+        })
+    }
+}
+
+/// Problems with how a port was written in the raw input
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum PortIssue {
+    /// Port `0`, which is reserved and almost never legitimate in a URL
+    Zero,
+    /// Port written with leading zeros (e.g. `080`), which parsers may read differently
+    LeadingZero(String),
+    /// Port outside `0..=65535`; the url crate rejects these outright
+    OutOfRange(String),
+}
+
+impl PortIssue {
+    /// Inspects the port exactly as written in the raw authority
+    fn detect(raw_port: &str) -> Option<Self> {
+        if raw_port.is_empty() || !raw_port.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        match raw_port.parse::<u16>() {
+            Err(_) => Some(PortIssue::OutOfRange(raw_port.to_string())),
+            Ok(0) => Some(PortIssue::Zero),
+            Ok(_) if raw_port.starts_with('0') => Some(PortIssue::LeadingZero(raw_port.to_string())),
+            Ok(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for PortIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PortIssue::Zero => write!(f, "port 0 is reserved"),
+            PortIssue::LeadingZero(port) => write!(f, "port '{}' has leading zeros", port),
+            PortIssue::OutOfRange(port) => write!(f, "port '{}' is out of range", port),
+        }
+    }
+}
+
+/// Half-open `(start, end)` byte ranges of URL components within the raw input string
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ComponentSpans {
+    pub scheme: Option<(usize, usize)>,
+    pub host: Option<(usize, usize)>,
+    pub path: Option<(usize, usize)>,
+    pub query: Option<(usize, usize)>,
+    pub fragment: Option<(usize, usize)>,
+}
+
+impl ComponentSpans {
+    /// Locates each component by scanning the raw input, so offsets refer to the text as the
+    /// caller wrote it rather than the normalized URL. Delimiters (`:`, `?`, `#`) are excluded.
+    pub fn compute(raw: &str) -> Self {
+        let mut spans = ComponentSpans::default();
+        let bytes = raw.as_bytes();
+        let start = raw.len() - raw.trim_start_matches(|c: char| c <= ' ').len();
+        let end = raw.trim_end_matches(|c: char| c <= ' ').len().max(start);
+
+        // Fragment and query are delimited from the right so they can be peeled off first
+        let fragment_at = raw[start..end].find('#').map(|i| i + start);
+        if let Some(hash) = fragment_at {
+            spans.fragment = Some((hash + 1, end));
+        }
+        let before_fragment = fragment_at.unwrap_or(end);
+        let query_at = raw[start..before_fragment].find('?').map(|i| i + start);
+        if let Some(question) = query_at {
+            spans.query = Some((question + 1, before_fragment));
+        }
+        let hierarchical_end = query_at.unwrap_or(before_fragment);
+
+        let mut cursor = start;
+        if let Some(colon) = raw[start..hierarchical_end].find(':').map(|i| i + start) {
+            let scheme = &raw[start..colon];
+            let valid = scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+            if valid {
+                spans.scheme = Some((start, colon));
+                cursor = colon + 1;
+            }
+        }
+
+        let is_slash = |b: u8| b == b'/' || b == b'\\';
+        if cursor + 1 < hierarchical_end && is_slash(bytes[cursor]) && is_slash(bytes[cursor + 1]) {
+            let authority_start = cursor + 2;
+            let authority_end = raw[authority_start..hierarchical_end]
+                .find(['/', '\\'])
+                .map(|i| i + authority_start)
+                .unwrap_or(hierarchical_end);
+
+            let host_start = raw[authority_start..authority_end]
+                .rfind('@')
+                .map(|i| i + authority_start + 1)
+                .unwrap_or(authority_start);
+            let host_end = if bytes.get(host_start) == Some(&b'[') {
+                raw[host_start..authority_end].find(']').map(|i| i + host_start + 1).unwrap_or(authority_end)
+            } else {
+                raw[host_start..authority_end].find(':').map(|i| i + host_start).unwrap_or(authority_end)
+            };
+            if host_end > host_start {
+                spans.host = Some((host_start, host_end));
+            }
+            cursor = authority_end;
+        }
+
+        if hierarchical_end > cursor {
+            spans.path = Some((cursor, hierarchical_end));
+        }
+
+        spans
+    }
+}
+
+/// ISO 639-1 two-letter language codes, sorted for binary search
+const ISO_639_1_CODES: &[&str] = &[
+    "aa", "ab", "ae", "af", "ak", "am", "an", "ar", "as", "av", "ay", "az", "ba", "be", "bg", "bh",
+    "bi", "bm", "bn", "bo", "br", "bs", "ca", "ce", "ch", "co", "cr", "cs", "cu", "cv", "cy", "da",
+    "de", "dv", "dz", "ee", "el", "en", "eo", "es", "et", "eu", "fa", "ff", "fi", "fj", "fo", "fr",
+    "fy", "ga", "gd", "gl", "gn", "gu", "gv", "ha", "he", "hi", "ho", "hr", "ht", "hu", "hy", "hz",
+    "ia", "id", "ie", "ig", "ii", "ik", "io", "is", "it", "iu", "ja", "jv", "ka", "kg", "ki", "kj",
+    "kk", "kl", "km", "kn", "ko", "kr", "ks", "ku", "kv", "kw", "ky", "la", "lb", "lg", "li", "ln",
+    "lo", "lt", "lu", "lv", "mg", "mh", "mi", "mk", "ml", "mn", "mr", "ms", "mt", "my", "na", "nb",
+    "nd", "ne", "ng", "nl", "nn", "no", "nr", "nv", "ny", "oc", "oj", "om", "or", "os", "pa", "pi",
+    "pl", "ps", "pt", "qu", "rm", "rn", "ro", "ru", "rw", "sa", "sc", "sd", "se", "sg", "si", "sk",
+    "sl", "sm", "sn", "so", "sq", "sr", "ss", "st", "su", "sv", "sw", "ta", "te", "tg", "th", "ti",
+    "tk", "tl", "tn", "to", "tr", "ts", "tt", "tw", "ty", "ug", "uk", "ur", "uz", "ve", "vi", "vo",
+    "wa", "wo", "xh", "yi", "yo", "za", "zh", "zu",
+];
+
+impl UrlComponents {
+    /// Returns the first path segment when it looks like a BCP-47 language tag: an ISO 639-1
+    /// language (`de`, `fr`), optionally followed by a 2-letter or 3-digit region joined by
+    /// `-` or `_` (`en-US`, `pt_BR`, `es-419`). A 3-letter language is only accepted with a
+    /// region (`fil-PH`), so segments like `/api/` and `/img/` are not mistaken for locales.
+    pub fn locale_prefix(&self) -> Option<String> {
+        let segment = self.path_segments.first()?;
+        let (language, region) = match segment.split_once(['-', '_']) {
+            Some((language, region)) => (language, Some(region)),
+            None => (segment.as_str(), None),
+        };
+
+        let language_ok = match language.len() {
+            2 => ISO_639_1_CODES.binary_search(&language.to_ascii_lowercase().as_str()).is_ok(),
+            3 => region.is_some() && language.chars().all(|c| c.is_ascii_alphabetic()),
+            _ => false,
+        };
+        let region_ok = region.is_none_or(|region| {
+            (region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic()))
+                || (region.len() == 3 && region.chars().all(|c| c.is_ascii_digit()))
+        });
+
+        (language_ok && region_ok).then(|| segment.clone())
+    }
+
+    /// Returns the first path segment that looks like an API version: `v`-prefixed (`v2`,
+    /// `V2.1`), bare dotted (`2.1`, `1.0.3`), or a date (`2024-01-01`)
+    pub fn api_version(&self) -> Option<String> {
+        let is_dotted = |text: &str| {
+            let parts: Vec<&str> = text.split('.').collect();
+            parts.len() <= 3 && parts.iter().all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+        };
+        let is_date = |text: &str| {
+            let parts: Vec<&str> = text.split('-').collect();
+            match parts.as_slice() {
+                [year, month, day] if year.len() == 4 && month.len() == 2 && day.len() == 2 => {
+                    let month = month.parse::<u8>().unwrap_or(0);
+                    let day = day.parse::<u8>().unwrap_or(0);
+                    year.bytes().all(|b| b.is_ascii_digit()) && (1..=12).contains(&month) && (1..=31).contains(&day)
+                }
+                _ => false,
+            }
+        };
+
+        self.path_segments.iter()
+            .find(|segment| {
+                match segment.strip_prefix(['v', 'V']) {
+                    Some(version) => is_dotted(version),
+                    None => (segment.contains('.') && is_dotted(segment)) || is_date(segment),
+                }
+            })
+            .cloned()
+    }
+
+    /// Returns the lowercased extension of the final path segment (`pdf` for
+    /// `/files/Report.PDF`). Paths ending in `/` and dotfiles have no extension.
+    pub fn file_extension(&self) -> Option<String> {
+        if self.path.ends_with('/') {
+            return None;
+        }
+        let (stem, extension) = self.path_segments.last()?.rsplit_once('.')?;
+        let valid = !stem.is_empty() && !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric());
+        valid.then(|| extension.to_ascii_lowercase())
+    }
+
+    /// Returns the last non-empty path segment (`document.pdf` from `/files/2024/document.pdf`),
+    /// ignoring any trailing slash. The root path has no resource name.
+    pub fn resource_name(&self) -> Option<String> {
+        self.path_segments.last().cloned()
+    }
+    
+    /// Returns the host with its labels reversed (`api.example.com` -> `com.example.api`).
+    /// IP hosts have no label hierarchy, so they yield `None`.
+    pub fn reverse_host(&self) -> Option<String> {
+        let host = self.host.as_deref()?;
+        if is_ip_host(host) {
+            return None;
+        }
+
+        let labels: Vec<&str> = host.trim_end_matches('.').split('.').rev().collect();
+        Some(labels.join("."))
+    }
+
+    /// Returns the index and kind of every path segment that looks like an identifier
+    pub fn path_ids(&self) -> Vec<(usize, IdKind)> {
+        self.path_segments.iter()
+            .enumerate()
+            .filter_map(|(index, segment)| IdKind::classify(segment).map(|kind| (index, kind)))
+            .collect()
+    }
+
+    /// Returns a copy with the values of sensitive query parameters replaced by `***`, for
+    /// logging. Keys are matched case-insensitively against `SENSITIVE_PARAMS` plus `extra`.
+    /// Other parameters keep their original encoding; `component_spans` is dropped since the
+    /// offsets no longer apply.
+    pub fn redact_sensitive_params(&self, extra: &[&str]) -> UrlComponents {
+        let is_sensitive = |key: &str| {
+            SENSITIVE_PARAMS.iter().chain(extra).any(|sensitive| key.eq_ignore_ascii_case(sensitive))
+        };
+        let mut redacted = self.clone();
+
+        redacted.query_params = self.query_params.iter()
+            .map(|(key, value)| {
+                let value = if is_sensitive(key) { "***".to_string() } else { value.clone() };
+                (key.clone(), value)
+            })
+            .collect();
+        redacted.query = self.query.as_ref().map(|query| {
+            query.split('&')
+                .map(|pair| {
+                    let raw_key = pair.split_once('=').map_or(pair, |(key, _)| key);
+                    let key = url::form_urlencoded::parse(raw_key.as_bytes()).next().map(|(key, _)| key);
+                    match key {
+                        Some(key) if is_sensitive(&key) => format!("{}=***", raw_key),
+                        _ => pair.to_string(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("&")
+        });
+        redacted.component_spans = None;
+
+        redacted
+    }
+
+    /// Picks the query parameter most likely to carry the redirect target among decoys.
+    ///
+    /// Each parameter scores key + value points and the highest total wins (first on ties):
+    /// - key: 3 for a well-known redirect name (`url`, `redirect`, `next`, `dest`, ...), 2 when
+    ///   it merely contains `url`, `redirect` or `return`, otherwise 0
+    /// - value: 3 for an absolute URL with a host, 2 for `//host/...`, 1 for a bare domain or
+    ///   (only under a redirect-ish key) a `/path`, otherwise 0
+    ///
+    /// Parameters whose value scores 0 are never chosen.
+    pub fn primary_redirect_param(&self) -> Option<(String, String)> {
+        const STRONG_KEYS: &[&str] = &[
+            "url", "uri", "redirect", "redirect_uri", "redirect_url", "redirecturl", "next", "dest",
+            "destination", "target", "return", "return_to", "returnto", "returnurl", "continue", "goto",
+            "to", "out", "forward", "u", "r",
+        ];
+
+        let score = |key: &str, value: &str| {
+            let key = key.to_ascii_lowercase();
+            let key_score = if STRONG_KEYS.contains(&key.as_str()) {
+                3
+            } else if ["url", "redirect", "return"].iter().any(|hint| key.contains(hint)) {
+                2
+            } else {
+                0
+            };
+            let value_score = if value.contains("://") && Url::parse(value).is_ok_and(|url| url.host_str().is_some()) {
+                3
+            } else if value.strip_prefix("//").is_some_and(|rest| looks_like_domain(rest.split('/').next().unwrap_or_default())) {
+                2
+            } else if looks_like_domain(value) || (key_score > 0 && value.starts_with('/')) {
+                1
+            } else {
+                0
+            };
+            (value_score > 0).then_some(key_score + value_score)
+        };
+
+        self.query_params.iter()
+            .filter_map(|(key, value)| score(key, value).map(|total| (total, key, value)))
+            .fold(None, |best: Option<(usize, &String, &String)>, candidate| match best {
+                Some(best) if best.0 >= candidate.0 => Some(best),
+                _ => Some(candidate),
+            })
+            .map(|(_, key, value)| (key.clone(), value.clone()))
+    }
+
+    /// Returns the keys of likely cache-buster parameters, in query order. A parameter counts
+    /// when its key is a conventional cache-buster name (`_`, `v`, `cb`, `ts`, `rnd`, ...) and
+    /// its value looks volatile: a 9+ digit number (Unix seconds or milliseconds) or an 8+
+    /// character alphanumeric token with Shannon entropy of at least 3 bits per character.
+    /// Feed the result to `UrlAnalysis::structural_fingerprint` as the volatile keys.
+    pub fn cache_buster_params(&self) -> Vec<String> {
+        const CACHE_BUSTER_KEYS: &[&str] = &[
+            "_", "v", "cb", "ts", "t", "rnd", "rand", "random", "nocache", "cachebuster",
+            "cache_buster", "cache", "_dc", "timestamp", "nonce", "bust",
+        ];
+
+        let is_volatile = |value: &str| {
+            let timestamp_like = value.len() >= 9 && value.bytes().all(|b| b.is_ascii_digit());
+            let random_token = value.len() >= 8
+                && value.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+                && shannon_entropy(value) >= 3.0;
+            timestamp_like || random_token
+        };
+
+        self.query_params.iter()
+            .filter(|(key, value)| CACHE_BUSTER_KEYS.contains(&key.to_ascii_lowercase().as_str()) && is_volatile(value))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Returns the Jaccard index of the two URLs' distinct `(key, value)` pairs: 1.0 for the
+    /// same set, 0.0 for disjoint sets. Two URLs with no query at all count as identical (1.0).
+    pub fn query_similarity(&self, other: &UrlComponents) -> f64 {
+        let ours: std::collections::HashSet<&(String, String)> = self.query_params.iter().collect();
+        let theirs: std::collections::HashSet<&(String, String)> = other.query_params.iter().collect();
+
+        let union = ours.union(&theirs).count();
+        if union == 0 {
+            return 1.0;
+        }
+        ours.intersection(&theirs).count() as f64 / union as f64
+    }
+
+    /// Returns the query in canonical form for request signing and cache keys, following the
+    /// AWS SigV4 rules: each key and value is decoded (`+` counts as a space) and re-encoded
+    /// with `encode_query_component`, so only `A-Z a-z 0-9 - . _ ~` stay literal and spaces
+    /// become `%20`; pairs are sorted by encoded key, then encoded value; a valueless `flag`
+    /// becomes `flag=`. No query gives an empty string.
+    pub fn canonical_query(&self) -> String {
+        let mut pairs: Vec<(String, String)> = self.query_params.iter()
+            .map(|(key, value)| (encode_query_component(key), encode_query_component(value)))
+            .collect();
+        pairs.sort();
+
+        pairs.iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// `scheme://[userinfo@]host[:port]` with scheme and host lowercased and the default port
+    /// omitted for WHATWG special schemes only (the ones the url crate itself strips), so
+    /// widening `well_known_port` never changes `cache_key` for `postgres://db:5432` and the like
+    fn canonical_origin(&self) -> String {
+        let mut origin = format!("{}://", self.scheme.to_ascii_lowercase());
+
+        if !self.username.is_empty() || self.password.is_some() {
+            origin.push_str(&self.username);
+            if let Some(password) = &self.password {
+                origin.push(':');
+                origin.push_str(password);
+            }
+            origin.push('@');
+        }
+        if let Some(host) = &self.host {
+            origin.push_str(&host.to_ascii_lowercase());
+        }
+        let default_port = SPECIAL_SCHEMES.contains(&self.scheme.to_ascii_lowercase().as_str())
+            .then(|| well_known_port(&self.scheme))
+            .flatten();
+        if let Some(port) = self.port.filter(|port| Some(*port) != default_port) {
+            origin.push_str(&format!(":{}", port));
+        }
+
+        origin
+    }
+
+    /// Returns the decoded query parameters as an ordered multimap
+    pub fn query_map(&self) -> QueryMap {
+        QueryMap::from_pairs(self.query_params.clone())
+    }
+
+    /// Collapses `query_params` into one entry per key, sorted by key, with each key's values
+    /// in the order they appeared (`?tag=a&tag=b` gives `tag => [a, b]`)
+    pub fn query_params_grouped(&self) -> std::collections::BTreeMap<String, Vec<String>> {
+        let mut grouped: std::collections::BTreeMap<String, Vec<String>> = std::collections::BTreeMap::new();
+        for (key, value) in &self.query_params {
+            grouped.entry(key.clone()).or_default().push(value.clone());
+        }
+        grouped
+    }
+
+    /// Percent-decodes the first value for `key` by one more layer. `query_params` values
+    /// are already form-decoded once by the url crate (`primary=https%3A%2F%2Fexample.com`
+    /// is stored as `https://example.com`), so this only matters for double-encoded values:
+    /// `%253A` is stored as `%3A` and returned as `:`. Each layer needs its own call to
+    /// `percent_decode_once`, so nested encodings are never over-decoded.
+    pub fn decoded_value(&self, key: &str) -> Option<String> {
+        self.query_params.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| percent_decode_once(value))
+    }
+
+    /// Checks `host_unicode` for labels that mix writing systems, the usual homograph trick
+    /// (`аpple.com` with a Cyrillic `а`). Returns `None` when every label sticks to one script,
+    /// as genuine IDNs like `пример.com` or `münchen.de` do; Latin mixed with Japanese or
+    /// Korean scripts is also accepted.
+    pub fn detect_mixed_script(&self) -> Option<MixedScriptReport> {
+        let host = self.host_unicode.as_deref()?;
+        let mut scripts = std::collections::BTreeSet::new();
+        let mut mixed_labels = Vec::new();
+
+        for label in host.split('.') {
+            let label_scripts: std::collections::BTreeSet<UnicodeScript> = label.chars().filter_map(UnicodeScript::of).collect();
+            if !UnicodeScript::is_accepted_mix(&label_scripts) {
+                mixed_labels.push(label.to_string());
+            }
+            scripts.extend(label_scripts);
+        }
+
+        if mixed_labels.is_empty() {
+            return None;
+        }
+
+        let confusables = host.chars()
+            .filter_map(|character| Some(ConfusableChar {
+                character,
+                looks_like: ascii_confusable(character)?,
+                script: UnicodeScript::of(character)?,
+            }))
+            .collect();
+
+        Some(MixedScriptReport {
+            scripts: scripts.into_iter().collect(),
+            mixed_labels,
+            confusables,
+        })
+    }
+
+    /// Returns `host:port` when an explicit port is present, otherwise just the host
+    pub fn host_with_port(&self) -> Option<String> {
+        let host = self.host.as_ref()?;
+        Some(match self.port {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.clone(),
+        })
+    }
+
+    /// The explicit port, or else the scheme's default (80 for `http`, 22 for `sftp`, 5432
+    /// for `postgres`, ...). `None` for schemes without a registered default port.
+    pub fn effective_port(&self) -> Option<u16> {
+        self.port.or_else(|| well_known_port(&self.scheme))
+    }
+
+    /// Like `host_with_port`, but falls back to the scheme's well-known port so the result
+    /// always names a concrete endpoint when the scheme has a default
+    pub fn effective_host_with_port(&self) -> Option<String> {
+        let host = self.host.as_ref()?;
+        Some(match self.effective_port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.clone(),
+        })
+    }
+
+    /// Names the service conventionally behind the effective port (`3306` is `mysql`,
+    /// `6379` is `redis`), using the default port table
+    pub fn well_known_service(&self) -> Option<String> {
+        self.well_known_service_with(&ServicePortMap::default())
+    }
+
+    /// Like `well_known_service`, with a caller-supplied port table. The effective port is
+    /// the explicit one or, failing that, the scheme's default.
+    pub fn well_known_service_with(&self, map: &ServicePortMap) -> Option<String> {
+        let port = self.effective_port()?;
+        map.get(port).map(str::to_string)
+    }
+
+    /// Returns true when an explicit port contradicts the scheme: it is another scheme's
+    /// well-known port, as in `http://example.com:443` or `https://example.com:80`.
+    /// Ordinary non-default ports such as `:8443` are not a mismatch, and only web and FTP
+    /// schemes are checked (`redis://cache:80` is just an unusual port).
+    pub fn scheme_port_mismatch(&self) -> bool {
+        if !matches!(self.scheme.as_str(), "http" | "https" | "ws" | "wss" | "ftp") {
+            return false;
+        }
+        let (Some(port), Some(expected)) = (self.port, well_known_port(&self.scheme)) else {
+            return false;
+        };
+        port != expected && ["http", "https", "ftp"].iter().any(|scheme| well_known_port(scheme) == Some(port))
+    }
+
+    /// Returns the host exactly as a TLS client sends it in SNI: ASCII (punycode), lowercased,
+    /// without port, userinfo or trailing dot. IP hosts return `None` since SNI omits them.
+    pub fn sni_host(&self) -> Option<String> {
+        let host = self.host.as_deref()?.trim_end_matches('.');
+        if host.is_empty() || is_ip_host(host) {
+            return None;
+        }
+
+        // Special schemes are already IDNA-encoded by the url crate; this covers opaque hosts
+        match url::Host::parse(host).ok()? {
+            url::Host::Domain(domain) => Some(domain.to_ascii_lowercase()),
+            url::Host::Ipv4(_) | url::Host::Ipv6(_) => None,
+        }
+    }
+
+    /// Tells a resolver how the host should be looked up, without doing any DNS. Only the
+    /// built-in internal suffixes count; use `TldAnalyzer::resolution_hint` to honour ones
+    /// added with `TldAnalyzer::with_internal_suffix`.
+    pub fn resolution_hint(&self) -> ResolutionHint {
+        self.resolution_hint_with(|host| {
+            DEFAULT_INTERNAL_SUFFIXES.iter().any(|suffix| host == *suffix || host.ends_with(&format!(".{}", suffix)))
+        })
+    }
+
+    fn resolution_hint_with(&self, is_internal: impl Fn(&str) -> bool) -> ResolutionHint {
+        let host = match self.host.as_deref() {
+            Some(host) if !host.is_empty() => host.trim_end_matches('.'),
+            _ => return ResolutionHint::NoHost,
+        };
+
+        if is_ip_host(host) {
+            ResolutionHint::IpLiteral
+        } else if is_internal(host) {
+            ResolutionHint::Internal
+        } else if !host.contains('.') {
+            ResolutionHint::SingleLabel
+        } else {
+            ResolutionHint::Fqdn
+        }
+    }
+
+    /// Returns true for unqualified hosts with no dots, like `localhost` or `intranet`, which
+    /// have no public suffix. Unlike `resolution_hint`, configured internal names still count.
+    pub fn is_single_label_host(&self) -> bool {
+        self.host.as_deref()
+            .map(|host| host.trim_end_matches('.'))
+            .is_some_and(|host| !host.is_empty() && !host.contains('.') && !is_ip_host(host))
+    }
+
+    /// Classifies the scheme by the kind of service it addresses
+    pub fn scheme_category(&self) -> SchemeCategory {
+        match self.scheme.as_str() {
+            "http" | "https" | "ws" | "wss" => SchemeCategory::Web,
+            "ftp" | "ftps" | "sftp" | "tftp" | "scp" => SchemeCategory::FileTransfer,
+            "mongodb" | "mongodb+srv" | "redis" | "rediss" | "postgres" | "postgresql"
+            | "mysql" | "mariadb" | "amqp" | "amqps" | "jdbc" => SchemeCategory::Database,
+            "git" | "git+ssh" | "git+https" | "ssh+git" | "svn" | "svn+ssh" | "hg" => SchemeCategory::VersionControl,
+            "ssh" | "telnet" | "rdp" | "vnc" => SchemeCategory::RemoteAccess,
+            "mailto" | "smtp" | "imap" | "pop3" => SchemeCategory::Mail,
+            "file" => SchemeCategory::File,
+            _ => SchemeCategory::Other,
+        }
+    }
+}
+
+/// Ordered multimap of query parameters that keeps duplicate keys and insertion order
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct QueryMap {
+    pairs: Vec<(String, String)>,
+}
+
+impl QueryMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_pairs(pairs: Vec<(String, String)>) -> Self {
+        Self { pairs }
+    }
+
+    /// Returns the first value for `key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Returns every value for `key`, in order
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.pairs.iter().filter(|(k, _)| k == key).map(|(_, v)| v.as_str()).collect()
+    }
+
+    /// Returns each distinct key once, in first-seen order
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = Vec::new();
+        for (key, _) in &self.pairs {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    /// Appends a value, keeping any existing values for the same key
+    pub fn insert(&mut self, key: &str, value: &str) {
+        self.pairs.push((key.to_string(), value.to_string()));
+    }
+
+    /// Removes every value for `key`, returning them in order
+    pub fn remove(&mut self, key: &str) -> Vec<String> {
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pairs)
+            .into_iter()
+            .partition(|(k, _)| k == key);
+        self.pairs = kept;
+        removed.into_iter().map(|(_, v)| v).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Serializes back to an `application/x-www-form-urlencoded` query string
+    pub fn to_query_string(&self) -> String {
+        url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(&self.pairs)
+            .finish()
+    }
+}
+
+/// Shape of an identifier found in a path segment
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdKind {
+    /// All ASCII digits (`12345`)
+    Numeric,
+    /// Canonical 8-4-4-4-12 hex UUID (`550e8400-e29b-41d4-a716-446655440000`)
+    Uuid,
+    /// Hex string of at least 8 characters, such as a hash or object ID
+    Hex,
+}
+
+impl IdKind {
+    pub fn classify(segment: &str) -> Option<Self> {
+        if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) {
+            return Some(IdKind::Numeric);
+        }
+
+        let groups: Vec<&str> = segment.split('-').collect();
+        let is_uuid = groups.len() == 5
+            && groups.iter().zip([8, 4, 4, 4, 12]).all(|(group, len)| {
+                group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit())
+            });
+        if is_uuid {
+            return Some(IdKind::Uuid);
+        }
+
+        if segment.len() >= 8 && segment.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Some(IdKind::Hex);
+        }
+
+        None
+    }
+}
+
+/// What kind of host a URL has
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HostType {
+    /// A registered or opaque name; the only kind that goes through TLD extraction
+    Domain,
+    /// An IPv4 address, including obfuscated forms decoded to dotted notation
+    Ipv4,
+    /// A bracketed IPv6 literal
+    Ipv6,
+    /// No host, or an empty one
+    #[default]
+    Unknown,
+}
+
+impl HostType {
+    fn of(host: Option<&str>) -> Self {
+        match host {
+            None | Some("") => HostType::Unknown,
+            Some(host) if host.parse::<std::net::Ipv4Addr>().is_ok() => HostType::Ipv4,
+            Some(host) if is_ip_host(host) => HostType::Ipv6,
+            Some(_) => HostType::Domain,
+        }
+    }
+
+    pub fn is_ip(self) -> bool {
+        matches!(self, HostType::Ipv4 | HostType::Ipv6)
+    }
+}
+
+/// How a host should be resolved
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionHint {
+    /// IPv4/IPv6 literal; no DNS lookup needed
+    IpLiteral,
+    /// Unqualified name that needs a search domain appended
+    SingleLabel,
+    /// Fully qualified name that can be resolved directly
+    Fqdn,
+    /// Name under an internal suffix such as `cluster.local`, for the private resolver
+    Internal,
+    /// The URL has no host
+    NoHost,
+}
+
+/// Broad grouping of URL schemes
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemeCategory {
+    Web,
+    FileTransfer,
+    Database,
+    VersionControl,
+    RemoteAccess,
+    Mail,
+    File,
+    Other,
+}
+
+/// Slim result of `FastDomainAnalyzer`: just the host and its public suffix split
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DomainResult {
+    pub host: Option<String>,
+    pub registrable_domain: Option<String>,
+    pub suffix: Option<String>,
+    pub subdomain: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TldComponents {
+    pub domain: Option<String>,
+    pub subdomain: Option<String>,
+    pub suffix: Option<String>,
+    #[serde(default)]
+    pub status: TldStatus,
+}
+
+/// Where the extracted suffix came from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TldStatus {
+    /// Suffix found in the public suffix list
+    Public,
+    /// Suffix matched one of the analyzer's configured internal suffixes (e.g. `cluster.local`)
+    Internal,
+    /// No suffix could be determined
+    #[default]
+    Unknown,
+    /// Extraction exceeded the analyzer's configured timeout
+    TimedOut,
+    /// The rightmost label is all digits but the host is not a valid IPv4 address
+    /// (e.g. `example.123`), so there is no meaningful suffix
+    NumericSuffix,
+}
+
+impl TldComponents {
+    /// Returns true when the host is the bare registrable domain (no subdomain, not even `www`)
+    pub fn is_apex(&self) -> bool {
+        self.subdomain.is_none() && self.domain.is_some() && self.suffix.is_some()
+    }
+
+    /// Returns `domain.suffix` when both parts were extracted
+    pub fn registrable_domain(&self) -> Option<String> {
+        match (&self.domain, &self.suffix) {
+            (Some(domain), Some(suffix)) => Some(format!("{}.{}", domain, suffix)),
+            _ => None,
+        }
+    }
+
+    /// `domain` with punycode decoded; the field itself keeps the ASCII form
+    pub fn domain_unicode(&self) -> Option<String> {
+        self.domain.as_deref().map(host_to_unicode)
+    }
+
+    /// `subdomain` with punycode decoded
+    pub fn subdomain_unicode(&self) -> Option<String> {
+        self.subdomain.as_deref().map(host_to_unicode)
+    }
+
+    /// `suffix` with punycode decoded, for IDN TLDs such as `xn--p1ai` (`рф`)
+    pub fn suffix_unicode(&self) -> Option<String> {
+        self.suffix.as_deref().map(host_to_unicode)
+    }
+
+    /// `registrable_domain` with punycode decoded
+    pub fn registrable_domain_unicode(&self) -> Option<String> {
+        self.registrable_domain().as_deref().map(host_to_unicode)
+    }
+    
+    /// Finds registrable-domain-shaped runs of labels inside the subdomain, the classic
+    /// brand-impersonation trick: `paypal.com.evil.ru` yields `["paypal.com"]` even though
+    /// the registrable domain is `evil.ru`. Results are in order of appearance, deduplicated.
+    /// Runs whose domain label is a common infrastructure name are ignored, so `api.dev` in
+    /// `api.dev.example.com` or `shop.app` in `shop.app.example.com` is not reported.
+    /// Candidates are checked with `tld_analyzer`, so pass the one used for extraction rather
+    /// than loading the suffix list again for every call.
+    pub fn subdomain_contains_domain_like(&self, tld_analyzer: &TldAnalyzer) -> Vec<String> {
+        // Labels that name a service or environment far more often than a brand, many of
+        // which collide with a TLD when followed by one (`api.dev`, `shop.app`, `mail.ru`)
+        const INFRASTRUCTURE_LABELS: &[&str] = &[
+            "admin", "api", "app", "apps", "auth", "beta", "blog", "cdn", "cloud", "dev", "docs",
+            "ftp", "git", "help", "img", "login", "m", "mail", "mx", "my", "ns", "portal", "prod",
+            "secure", "shop", "smtp", "sso", "stage", "staging", "static", "status", "store",
+            "support", "test", "vpn", "web", "webmail", "www",
+        ];
+
+        let Some(subdomain) = &self.subdomain else {
+            return Vec::new();
+        };
+        let labels: Vec<&str> = subdomain.split('.').filter(|label| !label.is_empty()).collect();
+        let mut found: Vec<String> = Vec::new();
+
+        for start in 0..labels.len() {
+            // A domain plus a suffix of up to three labels (e.g. `example.com.au`)
+            for end in (start + 2..=labels.len().min(start + 4)).rev() {
+                let candidate = labels[start..end].join(".");
+                let is_registrable = tld_analyzer.extract(&candidate).is_ok_and(|tld| {
+                    tld.status == TldStatus::Public
+                        && tld.subdomain.is_none()
+                        && tld.domain.as_deref().is_some_and(|domain| !INFRASTRUCTURE_LABELS.contains(&domain))
+                });
+                if is_registrable {
+                    if !found.contains(&candidate) {
+                        found.push(candidate);
+                    }
+                    break;
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Returns the suffix, the registrable domain and the registrable domain plus its
+    /// nearest subdomain label, for tiered grouping
+    pub fn domain_levels(&self) -> DomainLevels {
+        let etld_plus_1 = self.registrable_domain();
+        let etld_plus_2 = match (&self.subdomain, &etld_plus_1) {
+            (Some(subdomain), Some(registrable)) => subdomain.rsplit('.')
+                .next()
+                .filter(|label| !label.is_empty())
+                .map(|label| format!("{}.{}", label, registrable)),
+            _ => None,
+        };
+        
+        DomainLevels {
+            etld: self.suffix.clone(),
+            etld_plus_1,
+            etld_plus_2,
+        }
+    }
+}
+
+/// A host viewed at three depths, e.g. `co.uk`, `example.co.uk`, `mail.example.co.uk`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct DomainLevels {
+    pub etld: Option<String>,
+    pub etld_plus_1: Option<String>,
+    pub etld_plus_2: Option<String>,
+}
+
+/// Outcome of a single `DomainReputation` lookup
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReputationResult {
+    /// Risk score, higher is worse (the scale is provider-defined)
+    Score(f64),
+    /// The provider has no data for the domain
+    Unknown,
+    /// The lookup failed
+    Error(String),
+}
+
+/// Reputation of every registrable domain discovered in an analysis
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ReputationReport {
+    pub scores: std::collections::BTreeMap<String, ReputationResult>,
+}
+
+#[cfg(feature = "async")]
+impl ReputationReport {
+    /// Highest score across all domains, ignoring unknown and failed lookups
+    pub fn max_score(&self) -> Option<f64> {
+        self.scores.values()
+            .filter_map(|result| match result {
+                ReputationResult::Score(score) => Some(*score),
+                _ => None,
+            })
+            .reduce(f64::max)
+    }
+    
+    /// Domains whose lookup failed
+    pub fn errors(&self) -> Vec<&str> {
+        self.scores.iter()
+            .filter(|(_, result)| matches!(result, ReputationResult::Error(_)))
+            .map(|(domain, _)| domain.as_str())
+            .collect()
+    }
+}
+
+// ===== URL PARSER (Single Responsibility) =====
+
+/// Schemes whose URLs always carry an authority, so `scheme:host` is a typo for `scheme://host`
+const AUTHORITY_SCHEMES: &[&str] = &["http", "https", "ftp", "sftp", "ws", "wss", "ssh"];
+
+/// How internationalized hostnames are mapped to ASCII (see `UrlParser::with_idna_mode`)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdnaMode {
+    /// UTS #46 transitional processing, matching IDNA2003: the deviation characters are
+    /// mapped away, so `faß.de` becomes `fass.de`
+    Idna2003,
+    /// UTS #46 nontransitional processing, matching IDNA2008: deviation characters are kept,
+    /// so `faß.de` becomes `xn--fa-hia.de`. This is what the url crate does.
+    #[default]
+    Idna2008,
+}
+
+/// Handles pure URL parsing without TLD extraction
+pub struct UrlParser {
+    lenient: bool,
+    component_spans: bool,
+    preserve_backslashes: bool,
+    idna_mode: IdnaMode,
+}
+
+impl UrlParser {
+    pub fn new() -> Self {
+        Self {
+            lenient: false,
+            component_spans: false,
+            preserve_backslashes: false,
+            idna_mode: IdnaMode::default(),
+        }
+    }
+    
+    /// Chooses how non-ASCII hosts of special schemes map to ASCII.
+    ///
+    /// The default, `IdnaMode::Idna2008`, is the url crate's own behaviour and matches
+    /// current Chrome, Firefox and Safari. `IdnaMode::Idna2003` reproduces older browsers
+    /// and resolvers, which map `ß` to `ss`, `ς` to `σ` and drop ZWJ/ZWNJ; use it when a
+    /// comparison has to agree with such a client. Other characters are unaffected.
+    pub fn with_idna_mode(mut self, mode: IdnaMode) -> Self {
+        self.idna_mode = mode;
+        self
+    }
+    
+    /// Keeps backslashes literal instead of treating them as `/`.
+    ///
+    /// By default the url crate follows WHATWG, where `\` acts as a path separator for special
+    /// schemes (`http`, `https`, `ws`, `wss`, `ftp`, `file`), so `https://evil.com\@trusted.com`
+    /// has host `evil.com` as a browser would see it. With this option, backslashes before the
+    /// query are percent-encoded before parsing, giving the RFC 3986 reading that many
+    /// server-side clients use (host `trusted.com`). Either way `contained_backslashes` is set.
+    pub fn with_backslash_preservation(mut self) -> Self {
+        self.preserve_backslashes = true;
+        self
+    }
+    
+    /// Records the byte range of each component in the original input
+    pub fn with_component_spans(mut self) -> Self {
+        self.component_spans = true;
+        self
+    }
+    
+    /// Enables recovery from common hand-typed mistakes before parsing
+    pub fn with_lenient_parsing(mut self) -> Self {
+        self.lenient = true;
+        self
+    }
+    
+    /// Resolves `relative` against the first of `bases` that is itself absolute and can act as
+    /// a base (e.g. a page's `<base href>`, then the document URL), then parses the result.
+    /// An already-absolute `relative` is parsed as is. Fails with
+    /// `UrlAnalysisError::NoUsableBase` when no base works.
+    pub fn resolve_with_bases(&self, bases: &[&str], relative: &str) -> Result<(Url, UrlComponents), UrlAnalysisError> {
+        if Url::parse(relative.trim()).is_ok() {
+            return self.parse(relative);
+        }
+
+        let resolved = bases.iter()
+            .filter_map(|base| Url::parse(base.trim()).ok())
+            .find_map(|base| base.join(relative.trim()).ok())
+            .ok_or(UrlAnalysisError::NoUsableBase)?;
+        self.parse(resolved.as_str())
+    }
+
+    /// Resolves `url` against the absolute `base` with `Url::join` and parses the result, so
+    /// scheme-relative (`//cdn.example.com/x`) and path-relative (`/path/page`, `../a`) hrefs
+    /// scraped from a page can be analyzed. An absolute `url` replaces the base entirely.
+    /// Fails with `UrlAnalysisError::NoUsableBase` when `base` is not absolute.
+    pub fn parse_with_base(&self, base: &str, url: &str) -> Result<(Url, UrlComponents), UrlAnalysisError> {
+        self.parse(Self::join_base(base, url)?.as_str())
+    }
+
+    fn join_base(base: &str, url: &str) -> Result<Url, UrlAnalysisError> {
+        let base = Url::parse(base.trim()).map_err(|_| UrlAnalysisError::NoUsableBase)?;
+        Ok(base.join(url.trim())?)
+    }
+
+    /// Detects a malformed scheme separator (`https:/host` or `https:host`) and returns
+    /// the input split at the scheme and rejoined with `://`
+    pub fn repair_scheme_separator(url_str: &str) -> Option<String> {
+        let (scheme, rest) = url_str.split_once(':')?;
+        if !AUTHORITY_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()) || rest.starts_with("//") {
+            return None;
+        }
+        
+        let host_part = rest.strip_prefix('/').unwrap_or(rest);
+        if host_part.is_empty() || host_part.starts_with('/') {
+            return None;
+        }
+        
+        Some(format!("{}://{}", scheme, host_part))
+    }
+    
+    /// Applies lenient-mode fixes to the raw input, returning the input to parse and the fixes made.
+    /// Raw UNC paths are always rewritten, since they can never parse as URLs otherwise.
+    fn apply_repairs(&self, url_str: &str) -> (String, Vec<String>) {
+        let mut input = url_str.to_string();
+        let mut repairs = Vec::new();
+        
+        if let Some(file_url) = UncPath::to_file_url(&input) {
+            repairs.push(format!("UNC path converted: {} -> {}", input, file_url));
+            input = file_url;
+        }
+        
+        if !self.lenient {
+            return (input, repairs);
+        }
+        
+        if let Some(fixed) = Self::repair_scheme_separator(&input) {
+            repairs.push(format!("scheme separator corrected: {} -> {}", input, fixed));
+            input = fixed;
+        }
+        
+        (input, repairs)
+    }
+    
+    /// Canonical form for deduplication: lowercase scheme and host, no default port, query
+    /// parameters sorted by key then value, no `/` for a root-only path, and no fragment when
+    /// it is empty. URLs differing only in those respects normalize to the same string.
+    pub fn normalize(&self, url: &str) -> Result<String, UrlAnalysisError> {
+        let (_, components) = self.parse(url)?;
+        let path = if components.path == "/" { "" } else { components.path.as_str() };
+        let mut normalized = normalize_percent_case(&format!("{}{}", components.canonical_origin(), path));
+
+        if let Some(query) = sorted_form_query(&components.query_params) {
+            normalized.push('?');
+            normalized.push_str(&query);
+        }
+        if let Some(fragment) = components.fragment.as_deref().filter(|fragment| !fragment.is_empty()) {
+            normalized.push('#');
+            normalized.push_str(fragment);
+        }
+
+        Ok(normalized)
+    }
+    
+    pub fn parse(&self, url_str: &str) -> Result<(Url, UrlComponents), UrlAnalysisError> {
+        let (input, repairs) = self.apply_repairs(url_str);
+        
+        let port_issue = if self.lenient {
+            raw_authority(&input).and_then(raw_port).and_then(PortIssue::detect)
+        } else {
+            None
+        };
+        if let Some(issue @ PortIssue::OutOfRange(_)) = port_issue {
+            return Err(UrlAnalysisError::MalformedPort(issue));
+        }
+        
+        let input = if self.preserve_backslashes { encode_backslashes(&input) } else { input };
+        let input = match self.idna_mode {
+            IdnaMode::Idna2003 => map_idna_deviations(&input),
+            IdnaMode::Idna2008 => input,
+        };
+        let parsed_url = match Url::parse(&input) {
+            Ok(parsed_url) => parsed_url,
+            Err(url::ParseError::InvalidIpv4Address) => {
+                // Special schemes treat a numeric final label as IPv4; say why it was rejected
+                return Err(match raw_authority(url_str).map(raw_host).filter(|host| has_numeric_final_label(host)) {
+                    Some(host) => UrlAnalysisError::InvalidHost(format!(
+                        "'{}' ends in a numeric label but is not a valid IPv4 address", host
+                    )),
+                    None => url::ParseError::InvalidIpv4Address.into(),
+                });
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let deobfuscated_ip = raw_authority(url_str).map(raw_host).and_then(decode_obfuscated_ipv4);
+        
+        // Non-special schemes (mongodb, redis, ...) keep opaque hosts verbatim, so fold case here
+        // Special schemes already decode numeric IPv4 forms; opaque hosts need it done here
+        let host = deobfuscated_ip.map(|ip| ip.to_string())
+            .or_else(|| parsed_url.host_str().map(|s| s.to_ascii_lowercase()));
+        
+        let components = UrlComponents {
+            scheme: parsed_url.scheme().to_string(),
+            username: parsed_url.username().to_string(),
+            password: parsed_url.password().map(|s| s.to_string()),
+            host_type: HostType::of(host.as_deref()),
+            host_unicode: host.as_deref()
+                .filter(|_| HostType::of(host.as_deref()) == HostType::Domain)
+                .map(host_to_unicode),
+            host,
+            port: parsed_url.port(),
+            path: parsed_url.path().to_string(),
+            query: parsed_url.query().map(|s| s.to_string()),
+            fragment: parsed_url.fragment().map(|s| s.to_string()),
+            query_params: parsed_url.query_pairs()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            path_segments: parsed_url.path_segments()
+                .map(|segments| segments.filter(|s| !s.is_empty()).map(|s| s.to_string()).collect())
+                .unwrap_or_default(),
+            repairs,
+            component_spans: self.component_spans.then(|| ComponentSpans::compute(url_str)),
+            authority_ambiguous: raw_authority(url_str).is_some_and(is_ambiguous_authority),
+            port_issue,
+            unc: UncPath::from_file_url(&parsed_url),
+            contained_backslashes: contains_structural_backslash(url_str),
+            ip_was_obfuscated: deobfuscated_ip.is_some(),
+        };
+        
+        Ok((parsed_url, components))
+    }
+}
+
+impl Default for UrlParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ===== TLD ANALYZER (Single Responsibility) =====
+
+/// Private-network suffixes every `TldAnalyzer` treats as internal
+const DEFAULT_INTERNAL_SUFFIXES: &[&str] = &["cluster.local", "internal", "lan"];
+
+/// Widely used PRIVATE-section entries of the public suffix list. The snapshot bundled with
+/// `tldextract` holds the ICANN section only, so these are layered on top of it when an
+/// analyzer is not ICANN-only.
+const DEFAULT_PRIVATE_SUFFIXES: &[&str] = &[
+    "appspot.com", "azurewebsites.net", "blogspot.com", "cloudfront.net", "firebaseapp.com",
+    "github.io", "gitlab.io", "herokuapp.com", "netlify.app", "pages.dev", "vercel.app",
+    "web.app", "workers.dev",
+];
+
+#[cfg(test)]
+thread_local! {
+    /// Counts suffix list loads on this thread, so tests can check an analyzer is reused
+    pub(crate) static SUFFIX_LIST_LOADS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Handles TLD extraction separately from URL parsing
+pub struct TldAnalyzer {
+    extractor: std::sync::Arc<TldExtractor>,
+    internal_suffixes: Vec<String>,
+    private_suffixes: Vec<String>,
+    icann_only: bool,
+    timeout: Option<std::time::Duration>,
+    /// Stands in for a pathologically slow extraction in tests
+    #[cfg(test)]
+    artificial_delay: Option<std::time::Duration>,
+}
+
+impl TldAnalyzer {
+    pub fn new() -> Self {
+        Self::with_options(TldOption::default())
+    }
+
+    /// Creates an analyzer from raw `tldextract` options, e.g. which cache file the suffix
+    /// list is read from. See `TldAnalyzerBuilder` for a friendlier way to set them.
+    pub fn with_options(option: TldOption) -> Self {
+        #[cfg(test)]
+        SUFFIX_LIST_LOADS.with(|loads| loads.set(loads.get() + 1));
+        Self {
+            extractor: std::sync::Arc::new(TldExtractor::new(option)),
+            internal_suffixes: DEFAULT_INTERNAL_SUFFIXES.iter().map(|s| s.to_string()).collect(),
+            private_suffixes: DEFAULT_PRIVATE_SUFFIXES.iter().map(|s| s.to_string()).collect(),
+            icann_only: true,
+            timeout: None,
+            #[cfg(test)]
+            artificial_delay: None,
+        }
+    }
+
+    pub fn builder() -> TldAnalyzerBuilder {
+        TldAnalyzerBuilder::new()
+    }
+
+    /// Runs suffix extraction on a worker thread and gives up after `timeout`, reporting
+    /// `TldStatus::TimedOut` instead of stalling a batch. A timed-out worker is left to
+    /// finish in the background. Off by default.
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_artificial_delay(mut self, delay: std::time::Duration) -> Self {
+        self.artificial_delay = Some(delay);
+        self
+    }
+
+    /// Extracts on a worker thread, returning `None` if it does not finish within `timeout`
+    fn extract_with_timeout(
+        &self,
+        host: &str,
+        timeout: std::time::Duration,
+    ) -> Result<Option<tldextract::TldResult>, UrlAnalysisError> {
+        let extractor = std::sync::Arc::clone(&self.extractor);
+        let host = host.to_string();
+        #[cfg(test)]
+        let delay = self.artificial_delay;
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            #[cfg(test)]
+            if let Some(delay) = delay {
+                std::thread::sleep(delay);
+            }
+            // The receiver may already have given up; nothing to do if so
+            let _ = sender.send(extractor.extract(&host).map_err(|e| e.to_string()));
+        });
+
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result.map(Some).map_err(UrlAnalysisError::TldExtraction),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                Err(UrlAnalysisError::TldExtraction("worker thread panicked".to_string()))
+            }
+        }
+    }
+
+    /// Treats hosts under `suffix` as internal names rather than public domains
+    pub fn with_internal_suffix(mut self, suffix: &str) -> Self {
+        self.internal_suffixes.push(suffix.trim_matches('.').to_ascii_lowercase());
+        self
+    }
+
+    /// Chooses which sections of the public suffix list are used (ICANN-only is the default).
+    ///
+    /// With `true`, only ICANN suffixes count, so `user.github.io` has suffix `io` and
+    /// registrable domain `github.io`. With `false`, PRIVATE-section entries such as
+    /// `github.io` are suffixes too, so the registrable domain becomes `user.github.io` and
+    /// every GitHub Pages site groups separately.
+    pub fn with_icann_only(mut self, icann_only: bool) -> Self {
+        self.icann_only = icann_only;
+        self
+    }
+
+    /// Adds `suffix` to the PRIVATE-section suffixes honoured when the analyzer is not
+    /// ICANN-only, e.g. a hosting platform missing from the built-in list
+    pub fn with_private_suffix(mut self, suffix: &str) -> Self {
+        self.private_suffixes.push(suffix.trim_matches('.').to_ascii_lowercase());
+        self
+    }
+
+    /// Like `UrlComponents::resolution_hint`, treating every internal suffix configured on
+    /// this analyzer as `ResolutionHint::Internal`
+    pub fn resolution_hint(&self, components: &UrlComponents) -> ResolutionHint {
+        components.resolution_hint_with(|host| self.extract_internal(host).is_some())
+    }
+
+    /// Splits a host ending in a configured internal suffix
+    fn extract_internal(&self, host: &str) -> Option<TldComponents> {
+        split_on_suffix(host, &self.internal_suffixes, TldStatus::Internal)
+    }
+
+    /// Splits a host ending in a PRIVATE-section suffix unless the analyzer is ICANN-only
+    fn extract_private(&self, host: &str) -> Option<TldComponents> {
+        if self.icann_only {
+            return None;
+        }
+        split_on_suffix(host, &self.private_suffixes, TldStatus::Public)
+    }
+
+    /// Creates an analyzer that uses only the suffix snapshot bundled with `tldextract`: it
+    /// never fetches the list, never reads or writes a cache file (not even `./.tld_cache`)
+    /// and fails with `UrlAnalysisError::TldExtraction` if the snapshot cannot resolve a
+    /// well-known suffix.
+    pub fn offline() -> Result<Self, UrlAnalysisError> {
+        // A path containing NUL can never be opened, so the cache lookup always falls
+        // through to the snapshot
+        let analyzer = Self::with_options(TldOption::default().cache_path("\0").update_local(false));
+
+        let probe = analyzer.extract("example.co.uk")?;
+        if probe.suffix.as_deref() != Some("co.uk") {
+            return Err(UrlAnalysisError::TldExtraction(
+                "bundled public suffix list is unavailable".to_string(),
+            ));
+        }
+        Ok(analyzer)
+    }
+
+    /// Maps every registrable domain (eTLD+1) found in the host, query parameter values and
+    /// path segments of `analysis` to the places it was found, mirroring what
+    /// `WhoisFormatter` harvests
+    pub fn registrable_domains_with_sources(&self, analysis: &UrlAnalysis) -> std::collections::BTreeMap<String, Vec<EmbeddedSource>> {
+        let mut found: std::collections::BTreeMap<String, Vec<EmbeddedSource>> = std::collections::BTreeMap::new();
+        let registrable = |candidate: &str| self.extract(candidate).ok().and_then(|tld| tld.registrable_domain());
+
+        if let Some(domain) = analysis.tld_components.registrable_domain() {
+            found.entry(domain).or_default().push(EmbeddedSource::Host);
+        }
+
+        for (key, value) in &analysis.url_components.query_params {
+            let domain = match Url::parse(value) {
+                Ok(embedded_url) => embedded_url.host_str().and_then(registrable),
+                Err(_) if value.contains('.') && !value.starts_with('%') => registrable(value),
+                Err(_) => None,
+            };
+            if let Some(domain) = domain {
+                found.entry(domain).or_default().push(EmbeddedSource::QueryParam(key.clone()));
+            }
+        }
+
+        for (index, segment) in analysis.url_components.path_segments.iter().enumerate() {
+            if segment.contains('.') && !segment.starts_with('%') {
+                if let Some(domain) = registrable(segment) {
+                    found.entry(domain).or_default().push(EmbeddedSource::PathSegment(index));
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Every domain `WhoisFormatter` would print for `analysis`, checking embedded values
+    /// with this analyzer (see the `extract_all_domains` function)
+    pub fn extract_all_domains(&self, analysis: &UrlAnalysis, include_subdomains: bool) -> Vec<String> {
+        let mut domains = std::collections::BTreeSet::new();
+        collect_whois_domains(analysis, include_subdomains, self, &mut domains);
+        domains.into_iter().collect()
+    }
+
+    /// Splits `host` into subdomain, domain and suffix. IP literals yield no parts
+    /// (`TldStatus::Unknown`) rather than treating their last octet as a suffix.
+    pub fn extract(&self, host: &str) -> Result<TldComponents, UrlAnalysisError> {
+        if let Some(split) = self.extract_internal(host).or_else(|| self.extract_private(host)) {
+            return Ok(split);
+        }
+        
+        let status = if is_ip_host(host) {
+            Some(TldStatus::Unknown)
+        } else if has_numeric_final_label(host) {
+            Some(TldStatus::NumericSuffix)
+        } else {
+            None
+        };
+        if let Some(status) = status {
+            return Ok(TldComponents {
+                domain: None,
+                subdomain: None,
+                suffix: None,
+                status,
+            });
+        }
+        
+        let extracted = match self.timeout {
+            None => self.extractor.extract(host)
+                .map_err(|e| UrlAnalysisError::TldExtraction(e.to_string()))?,
+            Some(timeout) => match self.extract_with_timeout(host, timeout)? {
+                Some(extracted) => extracted,
+                None => return Ok(TldComponents {
+                    domain: None,
+                    subdomain: None,
+                    suffix: None,
+                    status: TldStatus::TimedOut,
+                }),
+            },
+        };
+        // tldextract hands back punycode labels decoded; keep the parts ASCII like the host
+        let ascii = |part: Option<String>| part.filter(|s| !s.is_empty()).map(|s| host_to_ascii(&s));
+        let suffix = ascii(extracted.suffix);
+        let status = if suffix.is_some() { TldStatus::Public } else { TldStatus::Unknown };
+        
+        Ok(TldComponents {
+            domain: ascii(extracted.domain),
+            subdomain: ascii(extracted.subdomain),
+            suffix,
+            status,
+        })
+    }
+}
+
+/// Splits a host ending in one of `suffixes`, preferring the longest match
+fn split_on_suffix(host: &str, suffixes: &[String], status: TldStatus) -> Option<TldComponents> {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    let suffix = suffixes.iter()
+        .filter(|suffix| host == **suffix || host.ends_with(&format!(".{}", suffix)))
+        .max_by_key(|suffix| suffix.len())?;
+
+    let remainder = host[..host.len() - suffix.len()].trim_end_matches('.');
+    let (subdomain, domain) = match remainder.rsplit_once('.') {
+        Some((subdomain, domain)) => (Some(subdomain.to_string()), Some(domain.to_string())),
+        None => (None, Some(remainder.to_string()).filter(|d| !d.is_empty())),
+    };
+
+    Some(TldComponents {
+        domain,
+        subdomain,
+        suffix: Some(suffix.clone()),
+        status,
+    })
+}
+
+impl Default for TldAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Configures where `TldAnalyzer` gets the public suffix list from. For air-gapped use,
+/// point `with_cache_path` at a suffix list cache prepared on a connected machine.
+#[derive(Debug, Clone, Default)]
+pub struct TldAnalyzerBuilder {
+    cache_path: Option<String>,
+    private_domains: bool,
+    update_local: bool,
+}
+
+impl TldAnalyzerBuilder {
+    /// Starts from the same settings as `TldAnalyzer::new`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the suffix list from the `tldextract` cache file at `path` instead of
+    /// `./.tld_cache`. The file is a JSON array of suffix rules (`["com", "co.uk", "*.ck",
+    /// "!www.ck", ...]`), not a raw `public_suffix_list.dat`; when it is missing or
+    /// unreadable the bundled snapshot is used.
+    pub fn with_cache_path(mut self, path: &std::path::Path) -> Self {
+        self.cache_path = Some(path.to_string_lossy().into_owned());
+        self
+    }
+
+    /// Treats PRIVATE-section suffixes such as `blogspot.com` as suffixes, so
+    /// `user.blogspot.com` is its own registrable domain (see `TldAnalyzer::with_icann_only`).
+    /// Off by default.
+    pub fn with_private_domains(mut self, private_domains: bool) -> Self {
+        self.private_domains = private_domains;
+        self
+    }
+
+    /// Writes the loaded suffix list back to the cache path when the analyzer is built,
+    /// e.g. to seed a cache file from the bundled snapshot
+    pub fn with_cache_write_back(mut self, write_back: bool) -> Self {
+        self.update_local = write_back;
+        self
+    }
+
+    pub fn build(&self) -> TldAnalyzer {
+        let mut option = TldOption::default().update_local(self.update_local);
+        if let Some(path) = &self.cache_path {
+            option = option.cache_path(path);
+        }
+        TldAnalyzer::with_options(option).with_icann_only(!self.private_domains)
+    }
+}
+
+/// Differences between two public suffix list snapshots
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PslDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub added_exceptions: Vec<String>,
+    pub removed_exceptions: Vec<String>,
+}
+
+/// Parsed rules of a PSL file: regular (including wildcard) suffixes and `!` exception rules
+fn parse_suffix_list(contents: &str) -> (std::collections::BTreeSet<String>, std::collections::BTreeSet<String>) {
+    let mut suffixes = std::collections::BTreeSet::new();
+    let mut exceptions = std::collections::BTreeSet::new();
+
+    for line in contents.lines() {
+        // Only the first whitespace-delimited token on a line is the rule
+        let rule = match line.split_whitespace().next() {
+            Some(rule) if !rule.starts_with("//") => rule.to_lowercase(),
+            _ => continue,
+        };
+
+        if let Some(exception) = rule.strip_prefix('!') {
+            exceptions.insert(exception.to_string());
+        } else {
+            suffixes.insert(rule);
+        }
+    }
+
+    (suffixes, exceptions)
+}
+
+/// Lists suffixes added and removed between two public suffix list files, e.g. to see
+/// which TLDs were newly delegated after updating the list
+pub fn suffix_list_diff(old_psl: &std::path::Path, new_psl: &std::path::Path) -> Result<PslDiff, Box<dyn std::error::Error>> {
+    let (old_suffixes, old_exceptions) = parse_suffix_list(&std::fs::read_to_string(old_psl)?);
+    let (new_suffixes, new_exceptions) = parse_suffix_list(&std::fs::read_to_string(new_psl)?);
+
+    Ok(PslDiff {
+        added: new_suffixes.difference(&old_suffixes).cloned().collect(),
+        removed: old_suffixes.difference(&new_suffixes).cloned().collect(),
+        added_exceptions: new_exceptions.difference(&old_exceptions).cloned().collect(),
+        removed_exceptions: old_exceptions.difference(&new_exceptions).cloned().collect(),
+    })
+}
+
+// ===== COMPOSITE ANALYZER =====
+
+/// Combines URL parsing and TLD extraction
+pub struct ComprehensiveUrlAnalyzer {
+    url_parser: UrlParser,
+    tld_analyzer: TldAnalyzer,
+    scan_json_params: bool,
+    max_embedded_depth: usize,
+    max_subdomain_labels: Option<usize>,
+    allowed_schemes: Option<Vec<String>>,
+}
+
+impl ComprehensiveUrlAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            url_parser: UrlParser::new(),
+            tld_analyzer: TldAnalyzer::new(),
+            scan_json_params: false,
+            max_embedded_depth: DEFAULT_MAX_EMBEDDED_DEPTH,
+            max_subdomain_labels: None,
+            allowed_schemes: None,
+        }
+    }
+
+    /// Recursively analyzes URLs found in query parameters and path segments, up to `depth`
+    /// levels deep (`DEFAULT_MAX_EMBEDDED_DEPTH` unless set; 0 disables the scan). Chains that
+    /// loop back to an endpoint already visited stop early and set `cycle_detected`.
+    pub fn with_max_embedded_depth(mut self, depth: usize) -> Self {
+        self.max_embedded_depth = depth;
+        self
+    }
+
+    /// Rejects URLs whose subdomain has more than `max` labels with
+    /// `UrlAnalysisError::TooManySubdomainLabels`; crafted hosts like
+    /// `a.b.c.d.e.f.g.example.com` are rarely legitimate. Unlimited by default.
+    pub fn with_max_subdomain_labels(mut self, max: usize) -> Self {
+        self.max_subdomain_labels = Some(max);
+        self
+    }
+
+    /// Rejects URLs whose scheme is not in `schemes` with
+    /// `UrlAnalysisError::UnsupportedScheme`, e.g. `&["http", "https"]` to keep `javascript:`
+    /// and `file:` out of a crawl queue. Schemes match case-insensitively. Any scheme by default.
+    pub fn with_allowed_schemes(mut self, schemes: &[&str]) -> Self {
+        self.allowed_schemes = Some(schemes.iter().map(|scheme| scheme.to_ascii_lowercase()).collect());
+        self
+    }
+
+    /// Scans query parameter values that hold JSON for nested URLs and domains
+    pub fn with_json_param_scanning(mut self) -> Self {
+        self.scan_json_params = true;
+        self
+    }
+
+    /// Recovers from common hand-typed mistakes (see [`UrlParser::with_lenient_parsing`])
+    pub fn with_lenient_parsing(mut self) -> Self {
+        self.url_parser = self.url_parser.with_lenient_parsing();
+        self
+    }
+    
+    /// Keeps backslashes literal when parsing (see `UrlParser::with_backslash_preservation`)
+    pub fn with_backslash_preservation(mut self) -> Self {
+        self.url_parser = self.url_parser.with_backslash_preservation();
+        self
+    }
+    
+    /// Chooses how non-ASCII hosts map to ASCII (see `UrlParser::with_idna_mode`)
+    pub fn with_idna_mode(mut self, mode: IdnaMode) -> Self {
+        self.url_parser = self.url_parser.with_idna_mode(mode);
+        self
+    }
+    
+    /// Analyzes a URL and scores each discovered registrable domain with `provider`.
+    /// Each domain is looked up once, in sorted order. The future is `Send`, so it can be
+    /// handed to `tokio::spawn` and the like.
+    #[cfg(feature = "async")]
+    pub async fn analyze_with_reputation<P: DomainReputation + Sync>(&self, url_str: &str, provider: &P) -> Result<(UrlAnalysis, ReputationReport), UrlAnalysisError> {
+        let analysis = self.analyze(url_str)?;
+        let mut report = ReputationReport::default();
+        
+        for domain in self.tld_analyzer.registrable_domains_with_sources(&analysis).into_keys() {
+            let result = provider.score(&domain).await;
+            report.scores.insert(domain, result);
+        }
+        
+        Ok((analysis, report))
+    }
+    
+    /// Analyzes a URL and triages it in one pass, returning non-fatal warnings alongside
+    pub fn analyze_with_warnings(&self, url_str: &str) -> Result<(UrlAnalysis, Vec<Warning>), UrlAnalysisError> {
+        let analysis = self.analyze(url_str)?;
+        let warnings = analysis.warnings();
+        Ok((analysis, warnings))
+    }
+    
+    /// Analyzes `url` resolved against `base` (see [`UrlParser::parse_with_base`]);
+    /// `original_url` holds the resolved absolute URL
+    pub fn analyze_relative(&self, base: &str, url: &str) -> Result<UrlAnalysis, UrlAnalysisError> {
+        self.analyze(UrlParser::join_base(base, url)?.as_str())
+    }
+    
+    /// Analyzes `url_str`, descending into embedded URLs while `remaining_depth` allows.
+    /// `ancestors` holds the endpoint keys of the chain above this URL for cycle detection.
+    fn analyze_nested(
+        &self,
+        url_str: &str,
+        remaining_depth: usize,
+        ancestors: &mut Vec<u64>,
+    ) -> Result<UrlAnalysis, UrlAnalysisError> {
+        let (_, url_components) = self.url_parser.parse(url_str)?;
+        if let Some(allowed) = &self.allowed_schemes {
+            if !allowed.contains(&url_components.scheme) {
+                return Err(UrlAnalysisError::UnsupportedScheme(url_components.scheme.clone()));
+            }
+        }
+        
+        // IP literals have no suffix to extract
+        let tld_components = if let (Some(host), HostType::Domain) = (url_components.host.as_deref(), url_components.host_type) {
+            self.tld_analyzer.extract(host)?
+        } else {
+            TldComponents {
+                domain: None,
+                subdomain: None,
+                suffix: None,
+                status: TldStatus::Unknown,
+            }
+        };
+        
+        if let (Some(max), Some(subdomain)) = (self.max_subdomain_labels, &tld_components.subdomain) {
+            let count = subdomain.split('.').count();
+            if count > max {
+                return Err(UrlAnalysisError::TooManySubdomainLabels { count, max });
+            }
+        }
+        
+        let mut embedded_hosts = Vec::new();
+        if self.scan_json_params {
+            for (_, value) in &url_components.query_params {
+                for host in extract_json_param_hosts(value) {
+                    if !embedded_hosts.contains(&host) {
+                        embedded_hosts.push(host);
+                    }
+                }
+            }
+        }
+        
+        let mut analysis = UrlAnalysis {
+            schema_version: SCHEMA_VERSION,
+            original_url: url_str.to_string(),
+            url_components,
+            tld_components,
+            embedded_hosts,
+            embedded_urls: Vec::new(),
+            source_param: None,
+            source_segment: None,
+            cycle_detected: false,
+        };
+        
+        let endpoint_key = analysis.endpoint_key();
+        if ancestors.contains(&endpoint_key) {
+            // The chain has looped back to an endpoint above us; stop descending here
+            analysis.cycle_detected = true;
+        } else if remaining_depth > 0 {
+            ancestors.push(endpoint_key);
+            for candidate in embedded_url_candidates(&analysis.url_components) {
+                if let Ok(mut nested) = self.analyze_nested(&candidate.url, remaining_depth - 1, ancestors) {
+                    nested.source_param = candidate.param;
+                    nested.source_segment = candidate.segment;
+                    analysis.cycle_detected |= nested.cycle_detected;
+                    analysis.embedded_urls.push(nested);
+                }
+            }
+            ancestors.pop();
+        }
+        
+        Ok(analysis)
+    }
+}
+
+/// How deep `ComprehensiveUrlAnalyzer` follows URLs nested inside URLs unless configured
+pub const DEFAULT_MAX_EMBEDDED_DEPTH: usize = 3;
+
+/// A URL found inside another URL, with where it was found
+struct EmbeddedCandidate {
+    url: String,
+    param: Option<String>,
+    segment: Option<usize>,
+}
+
+/// Collects URLs with a host from query parameter values and path segments, in that order,
+/// skipping repeats of a URL already collected. A segment counts when it decodes to a URL
+/// (`/go/https%3A%2F%2Fexample.com`) or is a bare `https:` followed by the rest of the path
+/// (`/go/https://example.com/page`).
+fn embedded_url_candidates(components: &UrlComponents) -> Vec<EmbeddedCandidate> {
+    let has_host = |value: &str| Url::parse(value).map(|url| url.host_str().is_some()).unwrap_or(false);
+    let mut candidates: Vec<EmbeddedCandidate> = Vec::new();
+    let mut push = |url: String, param: Option<String>, segment: Option<usize>| {
+        if !candidates.iter().any(|candidate| candidate.url == url) {
+            candidates.push(EmbeddedCandidate { url, param, segment });
+        }
+    };
+
+    for (key, value) in &components.query_params {
+        if has_host(value) {
+            push(value.clone(), Some(key.clone()), None);
+        }
+    }
+
+    for (index, segment) in components.path_segments.iter().enumerate() {
+        let decoded = percent_encoding::percent_decode_str(segment).decode_utf8_lossy();
+        if has_host(&decoded) {
+            push(decoded.into_owned(), None, Some(index));
+            continue;
+        }
+
+        let is_bare_scheme = decoded.strip_suffix(':')
+            .is_some_and(|scheme| !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)));
+        let rest = &components.path_segments[index + 1..];
+        if is_bare_scheme && !rest.is_empty() {
+            let url = format!("{}//{}", decoded, rest.join("/"));
+            if has_host(&url) {
+                push(url, None, Some(index));
+                break;
+            }
+        }
+    }
+
+    candidates
+}
+
+impl Default for ComprehensiveUrlAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UrlAnalyzer for ComprehensiveUrlAnalyzer {
+    type Output = UrlAnalysis;
+    type Error = UrlAnalysisError;
+    
+    fn analyze(&self, url_str: &str) -> Result<Self::Output, Self::Error> {
+        self.analyze_nested(url_str, self.max_embedded_depth, &mut Vec::new())
+    }
+}
+
+/// Domain-only analyzer for bulk extraction workloads.
+///
+/// Parses the URL and runs TLD extraction on the host, but never builds query parameters,
+/// path segments, credentials, repairs or any of the other `UrlComponents` extras, and never
+/// looks at embedded URLs. Compare against `analyze_url` with
+/// `cargo run --release --example fast_domain_bench`.
+pub struct FastDomainAnalyzer {
+    tld_analyzer: TldAnalyzer,
+}
+
+impl FastDomainAnalyzer {
+    pub fn new() -> Self {
+        Self {
+            tld_analyzer: TldAnalyzer::new(),
+        }
+    }
+}
+
+impl Default for FastDomainAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UrlAnalyzer for FastDomainAnalyzer {
+    type Output = DomainResult;
+    type Error = UrlAnalysisError;
+    
+    fn analyze(&self, url_str: &str) -> Result<Self::Output, Self::Error> {
+        let parsed_url = Url::parse(url_str.trim())?;
+        let Some(host) = parsed_url.host_str().map(|host| host.to_ascii_lowercase()) else {
+            return Ok(DomainResult { host: None, registrable_domain: None, suffix: None, subdomain: None });
+        };
+        
+        if is_ip_host(&host) {
+            return Ok(DomainResult { host: Some(host), registrable_domain: None, suffix: None, subdomain: None });
+        }
+        
+        let tld_components = self.tld_analyzer.extract(&host)?;
+        Ok(DomainResult {
+            registrable_domain: tld_components.registrable_domain(),
+            suffix: tld_components.suffix,
+            subdomain: tld_components.subdomain,
+            host: Some(host),
+        })
+    }
+}
+
+// ===== CLASSIFIERS =====
+
+/// Why a URL was classified as a tracking beacon
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum BeaconReason {
+    /// Host belongs to a known analytics/tracking domain
+    KnownDomain(String),
+    /// Query parameters match a known beacon signature (`key` or `key=value`)
+    ParamSignature(String),
+    /// Path looks like a tracking pixel or collection endpoint
+    PixelPath(String),
+}
+
+/// Result of running a `BeaconClassifier` over an analysis
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BeaconVerdict {
+    pub is_beacon: bool,
+    pub reasons: Vec<BeaconReason>,
+}
+
+/// Flags analytics beacons and tracking pixels by domain, parameter signature and path shape
+pub struct BeaconClassifier {
+    domains: Vec<String>,
+    params: Vec<(String, Option<String>)>,
+    pixel_paths: Vec<String>,
+}
+
+impl BeaconClassifier {
+    /// Creates a classifier with the default ruleset
+    pub fn new() -> Self {
+        Self {
+            domains: [
+                "google-analytics.com", "analytics.google.com", "doubleclick.net",
+                "googletagmanager.com", "scorecardresearch.com", "hotjar.com",
+                "segment.io", "mixpanel.com", "quantserve.com",
+            ].iter().map(|d| d.to_string()).collect(),
+            params: vec![
+                ("t".to_string(), Some("pageview".to_string())),
+                ("t".to_string(), Some("event".to_string())),
+                ("tid".to_string(), None),
+                ("_ga".to_string(), None),
+                ("ev".to_string(), Some("PageView".to_string())),
+            ],
+            pixel_paths: [
+                "/collect", "/g/collect", "/j/collect", "/tr", "/pixel", "/pixel.gif",
+                "/1x1.gif", "/beacon", "/p.gif", "/spacer.gif", "/__utm.gif",
+            ].iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    /// Creates a classifier with no rules, for callers supplying their own ruleset
+    pub fn empty() -> Self {
+        Self {
+            domains: Vec::new(),
+            params: Vec::new(),
+            pixel_paths: Vec::new(),
+        }
+    }
+
+    /// Adds a tracking domain; subdomains of it match too
+    pub fn with_domain(mut self, domain: &str) -> Self {
+        self.domains.push(domain.to_ascii_lowercase());
+        self
+    }
+
+    /// Adds a parameter signature; `None` matches the key with any value
+    pub fn with_param(mut self, key: &str, value: Option<&str>) -> Self {
+        self.params.push((key.to_string(), value.map(|v| v.to_string())));
+        self
+    }
+
+    /// Adds a path (matched exactly, case-insensitively) that identifies a pixel endpoint
+    pub fn with_pixel_path(mut self, path: &str) -> Self {
+        self.pixel_paths.push(path.to_ascii_lowercase());
+        self
+    }
+
+    pub fn classify(&self, analysis: &UrlAnalysis) -> BeaconVerdict {
+        let url = &analysis.url_components;
+        let mut reasons = Vec::new();
+
+        if let Some(host) = url.host.as_deref() {
+            let host = host.to_ascii_lowercase();
+            if let Some(domain) = self.domains.iter()
+                .find(|domain| host == **domain || host.ends_with(&format!(".{}", domain)))
+            {
+                reasons.push(BeaconReason::KnownDomain(domain.clone()));
+            }
+        }
+
+        for (key, expected) in &self.params {
+            let matched = url.query_params.iter().any(|(k, v)| {
+                k == key && expected.as_ref().is_none_or(|expected| v == expected)
+            });
+            if matched {
+                let signature = match expected {
+                    Some(value) => format!("{}={}", key, value),
+                    None => key.clone(),
+                };
+                reasons.push(BeaconReason::ParamSignature(signature));
+            }
+        }
+
+        let path = url.path.to_ascii_lowercase();
+        if self.pixel_paths.contains(&path) {
+            reasons.push(BeaconReason::PixelPath(url.path.clone()));
+        }
+
+        BeaconVerdict {
+            is_beacon: !reasons.is_empty(),
+            reasons,
+        }
+    }
+}
+
+impl Default for BeaconClassifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Structured entity recognized by `KnownSitePatterns`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum SiteData {
+    YouTubeVideo { id: String },
+    GitHubRepo { owner: String, repo: String },
+    TwitterHandle { handle: String },
+    RedditSubreddit { name: String },
+    /// Produced by extractors registered with `KnownSitePatterns::with_extractor`
+    Custom { site: String, fields: Vec<(String, String)> },
+}
+
+/// Extractor function stored by `KnownSitePatterns`
+type SiteExtractor = Box<dyn Fn(&UrlAnalysis) -> Option<SiteData> + Send + Sync>;
+
+/// Registry of per-site extractors that turn well-known URLs into `SiteData`
+pub struct KnownSitePatterns {
+    extractors: Vec<(String, SiteExtractor)>,
+}
+
+impl KnownSitePatterns {
+    /// Creates a registry with the built-in YouTube, GitHub, Twitter/X and Reddit patterns
+    pub fn new() -> Self {
+        Self::empty()
+            .with_extractor("youtube", extract_youtube)
+            .with_extractor("github", extract_github)
+            .with_extractor("twitter", extract_twitter)
+            .with_extractor("reddit", extract_reddit)
+    }
+
+    /// Creates a registry with no patterns, for callers supplying their own
+    pub fn empty() -> Self {
+        Self {
+            extractors: Vec::new(),
+        }
+    }
+
+    /// Registers an extractor; extractors run in registration order
+    pub fn with_extractor<F>(mut self, name: &str, extractor: F) -> Self
+    where
+        F: Fn(&UrlAnalysis) -> Option<SiteData> + Send + Sync + 'static,
+    {
+        self.extractors.push((name.to_string(), Box::new(extractor)));
+        self
+    }
+
+    /// Returns the first extractor's result that matches
+    pub fn extract(&self, analysis: &UrlAnalysis) -> Option<SiteData> {
+        self.extractors.iter().find_map(|(_, extractor)| extractor(analysis))
+    }
+}
+
+impl Default for KnownSitePatterns {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Host without a leading `www.` or `m.`, for matching site patterns
+fn site_host(analysis: &UrlAnalysis) -> Option<&str> {
+    let host = analysis.url_components.host.as_deref()?;
+    Some(host.strip_prefix("www.").or_else(|| host.strip_prefix("m.")).unwrap_or(host))
+}
+
+fn extract_youtube(analysis: &UrlAnalysis) -> Option<SiteData> {
+    let url = &analysis.url_components;
+    let segments: Vec<&str> = url.path_segments.iter().map(|s| s.as_str()).collect();
+    let id = match (site_host(analysis)?, segments.as_slice()) {
+        ("youtu.be", [id, ..]) => id.to_string(),
+        ("youtube.com" | "music.youtube.com", ["watch"]) => url.query_params.iter().find(|(key, _)| key == "v")?.1.clone(),
+        ("youtube.com", ["shorts" | "embed" | "live", id, ..]) => id.to_string(),
+        _ => return None,
+    };
+
+    let valid = id.len() == 11 && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(SiteData::YouTubeVideo { id })
+}
+
+fn extract_github(analysis: &UrlAnalysis) -> Option<SiteData> {
+    const RESERVED: &[&str] = &["orgs", "settings", "marketplace", "explore", "topics", "features", "login", "sponsors", "notifications", "about"];
+    if site_host(analysis)? != "github.com" {
+        return None;
+    }
+    let [owner, repo, ..] = analysis.url_components.path_segments.as_slice() else {
+        return None;
+    };
+    if RESERVED.contains(&owner.as_str()) {
+        return None;
+    }
+
+    Some(SiteData::GitHubRepo {
+        owner: owner.clone(),
+        repo: repo.strip_suffix(".git").unwrap_or(repo).to_string(),
+    })
+}
+
+fn extract_twitter(analysis: &UrlAnalysis) -> Option<SiteData> {
+    const RESERVED: &[&str] = &["home", "i", "search", "explore", "settings", "intent", "hashtag", "share", "login", "messages", "notifications", "compose"];
+    if !matches!(site_host(analysis)?, "twitter.com" | "x.com" | "mobile.twitter.com") {
+        return None;
+    }
+    let handle = analysis.url_components.path_segments.first()?;
+    let valid = (1..=15).contains(&handle.len())
+        && handle.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !RESERVED.contains(&handle.to_ascii_lowercase().as_str());
+
+    valid.then(|| SiteData::TwitterHandle { handle: handle.clone() })
+}
+
+fn extract_reddit(analysis: &UrlAnalysis) -> Option<SiteData> {
+    if !matches!(site_host(analysis)?, "reddit.com" | "old.reddit.com") {
+        return None;
+    }
+    match analysis.url_components.path_segments.as_slice() {
+        [r, name, ..] if r == "r" => Some(SiteData::RedditSubreddit { name: name.clone() }),
+        _ => None,
+    }
+}
+
+/// Groups registrable domains owned by the same organization (`google.com`, `youtube.com`,
+/// `googleapis.com`) so redirect analysis can treat intra-org hops as safe. Starts empty.
+#[derive(Debug, Clone, Default)]
+pub struct OrgMatcher {
+    groups: Vec<std::collections::HashSet<String>>,
+}
+
+impl OrgMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a group of registrable domains belonging to one organization
+    pub fn with_group(mut self, domains: &[&str]) -> Self {
+        self.groups.push(domains.iter().map(|domain| domain.trim_end_matches('.').to_ascii_lowercase()).collect());
+        self
+    }
+
+    /// Returns true when both URLs share a registrable domain, or their registrable domains
+    /// are in the same group. URLs without a registrable domain only match by host.
+    pub fn same_org(&self, a: &UrlAnalysis, b: &UrlAnalysis) -> bool {
+        let site = |analysis: &UrlAnalysis| {
+            analysis.tld_components.registrable_domain()
+                .or_else(|| analysis.url_components.host.clone())
+                .map(|site| site.to_ascii_lowercase())
+        };
+        let (Some(a), Some(b)) = (site(a), site(b)) else {
+            return false;
+        };
+
+        a == b || self.groups.iter().any(|group| group.contains(&a) && group.contains(&b))
+    }
+}
+
+/// Typosquat lookup over a brand list. Brand domains are indexed in a BK-tree keyed on
+/// Levenshtein distance, so `nearest` only visits subtrees the triangle inequality can't rule
+/// out instead of comparing against every brand.
+#[derive(Debug, Clone, Default)]
+pub struct BrandGuard {
+    nodes: Vec<BrandNode>,
+}
+
+#[derive(Debug, Clone)]
+struct BrandNode {
+    domain: String,
+    /// (distance to this node's domain, child node index)
+    children: Vec<(u32, usize)>,
+}
+
+impl BrandGuard {
+    /// Builds the index from brand registrable domains (case-insensitive, duplicates ignored)
+    pub fn new(brands: &[&str]) -> Self {
+        let mut guard = Self::default();
+        for brand in brands {
+            guard.insert(&brand.trim_end_matches('.').to_ascii_lowercase());
+        }
+        guard
+    }
+
+    fn insert(&mut self, domain: &str) {
+        if self.nodes.is_empty() {
+            self.nodes.push(BrandNode { domain: domain.to_string(), children: Vec::new() });
+            return;
+        }
+
+        let mut current = 0;
+        loop {
+            let distance = levenshtein_distance(domain, &self.nodes[current].domain);
+            if distance == 0 {
+                return;
+            }
+            match self.nodes[current].children.iter().find(|(d, _)| *d == distance) {
+                Some(&(_, child)) => current = child,
+                None => {
+                    let index = self.nodes.len();
+                    self.nodes.push(BrandNode { domain: domain.to_string(), children: Vec::new() });
+                    self.nodes[current].children.push((distance, index));
+                    return;
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns every brand within `max_distance` edits of `domain`, closest first (ties by
+    /// name). An exact brand match is included at distance 0.
+    pub fn nearest(&self, domain: &str, max_distance: u32) -> Vec<(String, u32)> {
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+        let mut matches = Vec::new();
+        let mut pending = if self.nodes.is_empty() { Vec::new() } else { vec![0] };
+
+        while let Some(index) = pending.pop() {
+            let node = &self.nodes[index];
+            let distance = levenshtein_distance(&domain, &node.domain);
+            if distance <= max_distance {
+                matches.push((node.domain.clone(), distance));
+            }
+            let range = distance.saturating_sub(max_distance)..=distance + max_distance;
+            pending.extend(node.children.iter()
+                .filter(|(edge, _)| range.contains(edge))
+                .map(|&(_, child)| child));
+        }
+
+        matches.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        matches
+    }
+}
+
+/// Matches hosts against domain patterns: `example.com` matches the domain and every
+/// subdomain, `*.example.com` matches subdomains only. Matching is case-insensitive and
+/// ignores a trailing dot.
+#[derive(Debug, Clone, Default)]
+pub struct DomainMatcher {
+    patterns: Vec<String>,
+}
+
+impl DomainMatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pattern(mut self, pattern: &str) -> Self {
+        self.patterns.push(pattern.trim().trim_end_matches('.').to_ascii_lowercase());
+        self
+    }
+
+    /// Returns the first pattern matching `host`
+    pub fn matches(&self, host: &str) -> Option<&str> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.patterns.iter()
+            .find(|pattern| match pattern.strip_prefix("*.") {
+                Some(parent) => host.ends_with(&format!(".{}", parent)),
+                None => host == **pattern || host.ends_with(&format!(".{}", pattern)),
+            })
+            .map(|pattern| pattern.as_str())
+    }
+}
+
+// ===== OUTPUT FORMATTERS =====
+
+/// JSON formatter
+pub struct JsonFormatter;
+
+impl JsonFormatter {
+    /// Streams analyses into `w` as a pretty JSON array without buffering the whole batch
+    pub fn write_array<W: std::io::Write>(
+        &self,
+        analyses: impl Iterator<Item = UrlAnalysis>,
+        w: &mut W,
+    ) -> Result<(), serde_json::Error> {
+        self.write_array_with(analyses, w, |_| Ok(()))
+    }
+
+    /// Like `write_array`, flushing at the interval configured on `w`
+    pub fn write_array_buffered<W: std::io::Write>(
+        &self,
+        analyses: impl Iterator<Item = UrlAnalysis>,
+        w: &mut BufferedFormatterWriter<W>,
+    ) -> Result<(), serde_json::Error> {
+        self.write_array_with(analyses, w, |w| w.end_record())
+    }
+
+    fn write_array_with<W, F>(
+        &self,
+        analyses: impl Iterator<Item = UrlAnalysis>,
+        w: &mut W,
+        mut after_record: F,
+    ) -> Result<(), serde_json::Error>
+    where
+        W: std::io::Write,
+        F: FnMut(&mut W) -> std::io::Result<()>,
+    {
+        w.write_all(b"[").map_err(serde_json::Error::io)?;
+
+        for (index, analysis) in analyses.enumerate() {
+            let separator: &[u8] = if index == 0 { b"\n" } else { b",\n" };
+            w.write_all(separator).map_err(serde_json::Error::io)?;
+            serde_json::to_writer_pretty(&mut *w, &analysis)?;
+            after_record(w).map_err(serde_json::Error::io)?;
+        }
+
+        w.write_all(b"\n]").map_err(serde_json::Error::io)?;
+        w.flush().map_err(serde_json::Error::io)
+    }
+}
+
+impl<T> OutputFormatter<T> for JsonFormatter 
+where 
+    T: Serialize 
+{
+    type Error = serde_json::Error;
+    
+    fn format(&self, data: &T) -> Result<String, Self::Error> {
+        serde_json::to_string_pretty(data)
+    }
+}
+
+/// Buffers formatter output and flushes the underlying writer after every N records,
+/// trading throughput against latency when piping to a slow consumer
+pub struct BufferedFormatterWriter<W: std::io::Write> {
+    inner: std::io::BufWriter<W>,
+    flush_every: usize,
+    records_since_flush: usize,
+}
+
+impl<W: std::io::Write> BufferedFormatterWriter<W> {
+    /// Wraps `inner`, flushing after every record until `flush_every` says otherwise
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner: std::io::BufWriter::new(inner),
+            flush_every: 1,
+            records_since_flush: 0,
+        }
+    }
+
+    /// Flushes after every `n` records; `0` flushes only when the writer is flushed explicitly
+    pub fn flush_every(mut self, n: usize) -> Self {
+        self.flush_every = n;
+        self
+    }
+
+    /// Marks the end of one record, flushing if the interval has been reached
+    pub fn end_record(&mut self) -> std::io::Result<()> {
+        use std::io::Write;
+        self.records_since_flush += 1;
+        if self.flush_every > 0 && self.records_since_flush >= self.flush_every {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes one record with any `OutputFormatter`, one record per line
+    pub fn write_formatted<T, F>(&mut self, formatter: &F, record: &T) -> std::io::Result<()>
+    where
+        F: OutputFormatter<T>,
+        F::Error: std::fmt::Display,
+    {
+        use std::io::Write;
+        let line = formatter.format(record)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error.to_string()))?;
+        self.inner.write_all(line.as_bytes())?;
+        self.inner.write_all(b"\n")?;
+        self.end_record()
+    }
+
+    /// Flushes any buffered output and returns the underlying writer
+    pub fn finish(mut self) -> std::io::Result<W> {
+        use std::io::Write;
+        self.flush()?;
+        self.inner.into_inner().map_err(|error| error.into_error())
+    }
+}
+
+impl<W: std::io::Write> std::io::Write for BufferedFormatterWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.records_since_flush = 0;
+        self.inner.flush()
+    }
+}
+
+/// Compact JSON formatter  
+pub struct CompactJsonFormatter;
+
+impl<T> OutputFormatter<T> for CompactJsonFormatter 
+where 
+    T: Serialize 
+{
+    type Error = serde_json::Error;
+    
+    fn format(&self, data: &T) -> Result<String, Self::Error> {
+        serde_json::to_string(data)
+    }
+}
+
+/// Whois-specific formatter - extracts domains for whois lookup
+pub struct WhoisFormatter {
+    include_subdomains: bool,
+    /// Shared by every extraction so the suffix list is loaded once per formatter
+    tld_analyzer: TldAnalyzer,
+}
+
+impl WhoisFormatter {
+    pub fn new() -> Self {
+        Self {
+            include_subdomains: false,
+            tld_analyzer: TldAnalyzer::new(),
+        }
+    }
+    
+    pub fn with_subdomains(mut self) -> Self {
+        self.include_subdomains = true;
+        self
+    }
+    
+    /// Uses `tld_analyzer` (e.g. one with internal suffixes or an offline list) for
+    /// extracting domains from query values and path segments
+    pub fn with_tld_analyzer(mut self, tld_analyzer: TldAnalyzer) -> Self {
+        self.tld_analyzer = tld_analyzer;
+        self
+    }
+}
+
+impl Default for WhoisFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputFormatter<Vec<UrlAnalysis>> for WhoisFormatter {
+    type Error = std::fmt::Error;
+    
+    fn format(&self, analyses: &Vec<UrlAnalysis>) -> Result<String, Self::Error> {
+        let mut domains = std::collections::BTreeSet::new();
+        for analysis in analyses {
+            collect_whois_domains(analysis, self.include_subdomains, &self.tld_analyzer, &mut domains);
+        }
+        Ok(domains.into_iter().collect::<Vec<_>>().join("\n"))
+    }
+}
+
+impl OutputFormatter<UrlAnalysis> for WhoisFormatter {
+    type Error = std::fmt::Error;
+    
+    fn format(&self, analysis: &UrlAnalysis) -> Result<String, Self::Error> {
+        self.format(&vec![analysis.clone()])
+    }
+}
+
+/// Adds the domains `WhoisFormatter` harvests from one analysis to `domains`: the host, hosts
+/// of URLs in query values, and domain-like query values and path segments. Registrable
+/// domains only, unless `include_subdomains` keeps full hosts.
+fn collect_whois_domains(
+    analysis: &UrlAnalysis,
+    include_subdomains: bool,
+    tld_analyzer: &TldAnalyzer,
+    domains: &mut std::collections::BTreeSet<String>,
+) {
+    // Extract the main domain for whois lookup
+    if let Some(host) = &analysis.url_components.host {
+        if include_subdomains {
+            // Include full domain with subdomains
+            domains.insert(host.clone());
+        } else {
+            // Extract just the registrable domain (domain + suffix)
+            if let (Some(domain), Some(suffix)) = (&analysis.tld_components.domain, &analysis.tld_components.suffix) {
+                domains.insert(format!("{}.{}", domain, suffix));
+            } else {
+                // Fallback to full host if TLD extraction failed
+                domains.insert(host.clone());
+            }
+        }
+    }
+
+    // Also extract domains from embedded URLs in query params
+    for (_, value) in &analysis.url_components.query_params {
+        // First try to parse as a full URL
+        if let Ok(embedded_url) = Url::parse(value) {
+            if let Some(embedded_host) = embedded_url.host_str() {
+                if include_subdomains {
+                    domains.insert(embedded_host.to_string());
+                } else {
+                    // Try to extract domain from embedded URL
+                    if let Ok(tld_components) = tld_analyzer.extract(embedded_host) {
+                        if let (Some(domain), Some(suffix)) = (tld_components.domain, tld_components.suffix) {
+                            domains.insert(format!("{}.{}", domain, suffix));
+                        }
+                    } else {
+                        domains.insert(embedded_host.to_string());
+                    }
+                }
+            }
+        } else if value.contains('.') && !value.starts_with('%') {
+            // If it's not a valid URL but looks like a domain, try to extract it
+            if let Ok(tld_components) = tld_analyzer.extract(value) {
+                if include_subdomains {
+                    // For subdomains, try to reconstruct the full domain if possible
+                    if let (Some(subdomain), Some(domain), Some(suffix)) = (&tld_components.subdomain, &tld_components.domain, &tld_components.suffix) {
+                        domains.insert(format!("{}.{}.{}", subdomain, domain, suffix));
+                    } else if let (Some(domain), Some(suffix)) = (&tld_components.domain, &tld_components.suffix) {
+                        domains.insert(format!("{}.{}", domain, suffix));
+                    }
+                } else {
+                    // Extract just the registrable domain
+                    if let (Some(domain), Some(suffix)) = (&tld_components.domain, &tld_components.suffix) {
+                        domains.insert(format!("{}.{}", domain, suffix));
+                    }
+                }
+            }
+        }
+    }
+
+    // Extract domains from path segments that look like domains
+    for segment in &analysis.url_components.path_segments {
+        if segment.contains('.') && !segment.starts_with('%') {
+            // This might be a domain in the path
+            if include_subdomains {
+                domains.insert(segment.clone());
+            } else if let Ok(tld_components) = tld_analyzer.extract(segment) {
+                if let (Some(domain), Some(suffix)) = (tld_components.domain, tld_components.suffix) {
+                    domains.insert(format!("{}.{}", domain, suffix));
+                }
+            }
+        }
+    }
+}
+
+/// Emits one deduplicated URL per line, in first-seen order, e.g. to feed a crawler
+pub struct UrlListFormatter {
+    normalize: bool,
+    strip_tracking: bool,
+    strip_fragments: bool,
+}
+
+impl UrlListFormatter {
+    pub fn new() -> Self {
+        Self {
+            normalize: true,
+            strip_tracking: false,
+            strip_fragments: false,
+        }
+    }
+    
+    /// Dedupes on `UrlAnalysis::normalized_url` (the default) or, when off, on the URL as written
+    pub fn with_normalization(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+    
+    /// Drops tracking parameters (see `is_tracking_param`) before deduping
+    pub fn with_tracking_stripped(mut self) -> Self {
+        self.strip_tracking = true;
+        self
+    }
+    
+    /// Drops fragments before deduping (normalized URLs never carry one)
+    pub fn with_fragments_stripped(mut self) -> Self {
+        self.strip_fragments = true;
+        self
+    }
+    
+    fn list_entry(&self, analysis: &UrlAnalysis) -> String {
+        let mut params = analysis.url_components.query_params.clone();
+        if self.strip_tracking {
+            params.retain(|(key, _)| !is_tracking_param(key));
+        }
+        
+        if self.normalize {
+            return analysis.canonical_url(&analysis.url_components.path, &params);
+        }
+        if !self.strip_tracking && !self.strip_fragments {
+            return analysis.original_url.clone();
+        }
+        
+        let Ok(mut url) = Url::parse(&analysis.original_url) else {
+            return analysis.original_url.clone();
+        };
+        if self.strip_fragments {
+            url.set_fragment(None);
+        }
+        if self.strip_tracking && params.len() != analysis.url_components.query_params.len() {
+            if params.is_empty() {
+                url.set_query(None);
+            } else {
+                url.query_pairs_mut().clear().extend_pairs(params);
+            }
+        }
+        url.to_string()
+    }
+}
+
+impl Default for UrlListFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputFormatter<Vec<UrlAnalysis>> for UrlListFormatter {
+    type Error = std::fmt::Error;
+    
+    fn format(&self, analyses: &Vec<UrlAnalysis>) -> Result<String, Self::Error> {
+        let mut seen = std::collections::HashSet::new();
+        let urls: Vec<String> = analyses.iter()
+            .map(|analysis| self.list_entry(analysis))
+            .filter(|url| seen.insert(url.clone()))
+            .collect();
+        
+        Ok(urls.join("\n"))
+    }
+}
+
+/// Summary-line formatter - one `scheme host registrable_domain path` line per URL, space
+/// separated for `awk`/`grep`. Absent or empty fields print as `-`; whitespace inside a field
+/// is percent-encoded so every line always has exactly four columns.
+pub struct SummaryLineFormatter;
+
+impl SummaryLineFormatter {
+    fn field(value: Option<&str>) -> String {
+        match value {
+            Some(value) if !value.is_empty() => value
+                .replace(' ', "%20")
+                .replace('\t', "%09")
+                .replace('\n', "%0A")
+                .replace('\r', "%0D"),
+            _ => "-".to_string(),
+        }
+    }
+
+    fn line(analysis: &UrlAnalysis) -> String {
+        let url = &analysis.url_components;
+        let registrable_domain = analysis.tld_components.registrable_domain();
+        [
+            Self::field(Some(&url.scheme)),
+            Self::field(url.host.as_deref()),
+            Self::field(registrable_domain.as_deref()),
+            Self::field(Some(&url.path)),
+        ].join(" ")
+    }
+}
+
+impl OutputFormatter<Vec<UrlAnalysis>> for SummaryLineFormatter {
+    type Error = std::fmt::Error;
+
+    fn format(&self, analyses: &Vec<UrlAnalysis>) -> Result<String, Self::Error> {
+        let lines: Vec<String> = analyses.iter().map(Self::line).collect();
+        Ok(lines.join("\n"))
+    }
+}
+
+/// CSV formatter - one row per analysis under a fixed header, escaped per RFC 4180.
+/// Query parameters (`key=value`) and path segments are each joined into a single cell.
+pub struct CsvFormatter {
+    delimiter: char,
+    value_separator: String,
+}
+
+impl CsvFormatter {
+    /// Column names, in output order
+    pub const HEADER: &'static [&'static str] = &[
+        "original_url", "scheme", "host", "port", "path", "domain", "subdomain", "suffix",
+        "query_params", "path_segments",
+    ];
+
+    pub fn new() -> Self {
+        Self {
+            delimiter: ',',
+            value_separator: ";".to_string(),
+        }
+    }
+
+    /// Separates fields with `delimiter` instead of `,` (e.g. `'\t'` for TSV)
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Joins the query parameters and path segments cells with `separator` instead of `;`
+    pub fn with_value_separator(mut self, separator: &str) -> Self {
+        self.value_separator = separator.to_string();
+        self
+    }
+
+    /// Quotes a field containing the delimiter, a quote or a line break, doubling inner quotes
+    fn escape(&self, field: &str) -> String {
+        if field.contains([self.delimiter, '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+
+    fn row(&self, fields: &[String]) -> String {
+        let escaped: Vec<String> = fields.iter().map(|field| self.escape(field)).collect();
+        escaped.join(&self.delimiter.to_string())
+    }
+}
+
+impl Default for CsvFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputFormatter<Vec<UrlAnalysis>> for CsvFormatter {
+    type Error = std::fmt::Error;
+
+    fn format(&self, analyses: &Vec<UrlAnalysis>) -> Result<String, Self::Error> {
+        let header: Vec<String> = Self::HEADER.iter().map(|column| column.to_string()).collect();
+        let mut lines = vec![self.row(&header)];
+
+        for analysis in analyses {
+            let url = &analysis.url_components;
+            let tld = &analysis.tld_components;
+            let query_params: Vec<String> = url.query_params.iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+
+            lines.push(self.row(&[
+                analysis.original_url.clone(),
+                url.scheme.clone(),
+                url.host.clone().unwrap_or_default(),
+                url.port.map(|port| port.to_string()).unwrap_or_default(),
+                url.path.clone(),
+                tld.domain.clone().unwrap_or_default(),
+                tld.subdomain.clone().unwrap_or_default(),
+                tld.suffix.clone().unwrap_or_default(),
+                query_params.join(&self.value_separator),
+                url.path_segments.join(&self.value_separator),
+            ]));
+        }
+
+        // RFC 4180 records end in CRLF
+        Ok(lines.join("\r\n"))
+    }
+}
+
+/// Top-level fields that a projection can select by name
+const PROJECTION_FIELDS: &[&str] = &[
+    "original_url", "scheme", "username", "password", "host", "port", "path",
+    "query", "fragment", "domain", "subdomain", "suffix",
+];
+
+/// A single field selector understood by `ProjectionFormatter`
+#[derive(Debug, Clone, PartialEq)]
+enum ProjectionSelector {
+    Field(String),
+    QueryParam(String),
+    PathSegment(usize),
+}
+
+impl ProjectionSelector {
+    fn parse(selector: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        if let Some(key) = selector.strip_prefix("query.") {
+            if key.is_empty() {
+                return Err(format!("projection selector '{}' is missing a query key", selector).into());
+            }
+            return Ok(ProjectionSelector::QueryParam(key.to_string()));
+        }
+
+        if let Some(index) = selector.strip_prefix("path[").and_then(|rest| rest.strip_suffix(']')) {
+            let index = index.parse::<usize>()
+                .map_err(|_| format!("projection selector '{}' has an invalid path index", selector))?;
+            return Ok(ProjectionSelector::PathSegment(index));
+        }
+
+        if PROJECTION_FIELDS.contains(&selector) {
+            Ok(ProjectionSelector::Field(selector.to_string()))
+        } else {
+            Err(format!("unknown projection selector '{}'", selector).into())
+        }
+    }
+
+    fn select(&self, analysis: &UrlAnalysis) -> serde_json::Value {
+        use serde_json::Value;
+
+        let url = &analysis.url_components;
+        let tld = &analysis.tld_components;
+        let optional = |value: &Option<String>| value.clone().map(Value::String).unwrap_or(Value::Null);
+
+        match self {
+            ProjectionSelector::Field(name) => match name.as_str() {
+                "original_url" => Value::String(analysis.original_url.clone()),
+                "scheme" => Value::String(url.scheme.clone()),
+                "username" => Value::String(url.username.clone()),
+                "password" => optional(&url.password),
+                "host" => optional(&url.host),
+                "port" => url.port.map(Value::from).unwrap_or(Value::Null),
+                "path" => Value::String(url.path.clone()),
+                "query" => optional(&url.query),
+                "fragment" => optional(&url.fragment),
+                "domain" => optional(&tld.domain),
+                "subdomain" => optional(&tld.subdomain),
+                "suffix" => optional(&tld.suffix),
+                _ => Value::Null,
+            },
+            ProjectionSelector::QueryParam(key) => url.query_params.iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| Value::String(v.clone()))
+                .unwrap_or(Value::Null),
+            ProjectionSelector::PathSegment(index) => url.path_segments.get(*index)
+                .map(|segment| Value::String(segment.clone()))
+                .unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// Projection formatter - emits a JSON object containing only the selected fields.
+/// Supports top-level field names plus `query.<key>` and `path[<n>]` selectors.
+pub struct ProjectionFormatter {
+    selectors: Vec<(String, ProjectionSelector)>,
+}
+
+impl ProjectionFormatter {
+    /// Builds a projection, rejecting any selector it does not understand
+    pub fn new(selectors: &[&str]) -> Result<Self, Box<dyn std::error::Error>> {
+        let selectors = selectors.iter()
+            .map(|selector| Ok((selector.to_string(), ProjectionSelector::parse(selector)?)))
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+
+        Ok(Self { selectors })
+    }
+}
+
+impl OutputFormatter<UrlAnalysis> for ProjectionFormatter {
+    type Error = serde_json::Error;
+
+    fn format(&self, analysis: &UrlAnalysis) -> Result<String, Self::Error> {
+        let mut object = serde_json::Map::new();
+        for (name, selector) in &self.selectors {
+            object.insert(name.clone(), selector.select(analysis));
+        }
+
+        serde_json::to_string(&object)
+    }
+}
+
+/// YAML formatter - the YAML counterpart of `JsonFormatter`; a `Vec` becomes a top-level
+/// sequence and a single analysis a mapping
+#[cfg(feature = "yaml")]
+pub struct YamlFormatter;
+
+#[cfg(feature = "yaml")]
+impl<T> OutputFormatter<T> for YamlFormatter
+where
+    T: Serialize
+{
+    type Error = serde_yaml::Error;
+
+    fn format(&self, data: &T) -> Result<String, Self::Error> {
+        serde_yaml::to_string(data)
+    }
+}
+
+/// STIX 2.1 formatter - emits discovered URLs and registrable domains as `url` and
+/// `domain-name` observables wrapped in a bundle
+#[cfg(feature = "stix")]
+pub struct StixFormatter;
+
+#[cfg(feature = "stix")]
+impl StixFormatter {
+    /// Namespace the STIX 2.1 spec mandates for deterministic (UUIDv5) cyber-observable IDs
+    const SCO_NAMESPACE: [u8; 16] = [
+        0x00, 0xab, 0xed, 0xb4, 0xaa, 0x42, 0x46, 0x6c,
+        0x9c, 0x01, 0xfe, 0xd2, 0x33, 0x15, 0xa9, 0xb7,
+    ];
+
+    /// Collects unique URLs and registrable domains from an analysis and its embedded URLs
+    fn collect_indicators(analysis: &UrlAnalysis, urls: &mut Vec<String>, domains: &mut Vec<String>) {
+        if !urls.contains(&analysis.original_url) {
+            urls.push(analysis.original_url.clone());
+        }
+        if let (Some(domain), Some(suffix)) = (&analysis.tld_components.domain, &analysis.tld_components.suffix) {
+            let registrable = format!("{}.{}", domain, suffix);
+            if !domains.contains(&registrable) {
+                domains.push(registrable);
+            }
+        }
+        for embedded in &analysis.embedded_urls {
+            Self::collect_indicators(embedded, urls, domains);
+        }
+    }
+
+    /// Builds a SCO whose ID is derived from its `value`, as the spec requires for these types
+    fn observable(object_type: &str, value: &str) -> serde_json::Value {
+        let contributing = serde_json::json!({ "value": value }).to_string();
+        let id = format!("{}--{}", object_type, uuid_v5(&Self::SCO_NAMESPACE, contributing.as_bytes()));
+        serde_json::json!({
+            "type": object_type,
+            "spec_version": "2.1",
+            "id": id,
+            "value": value,
+        })
+    }
+}
+
+#[cfg(feature = "stix")]
+impl OutputFormatter<Vec<UrlAnalysis>> for StixFormatter {
+    type Error = serde_json::Error;
+
+    fn format(&self, analyses: &Vec<UrlAnalysis>) -> Result<String, Self::Error> {
+        let mut urls = Vec::new();
+        let mut domains = Vec::new();
+        for analysis in analyses {
+            Self::collect_indicators(analysis, &mut urls, &mut domains);
+        }
+
+        let objects: Vec<serde_json::Value> = domains.iter()
+            .map(|domain| Self::observable("domain-name", domain))
+            .chain(urls.iter().map(|url| Self::observable("url", url)))
+            .collect();
+
+        // Derive the bundle ID from its contents so identical input yields identical output
+        let object_ids: Vec<&str> = objects.iter().filter_map(|object| object["id"].as_str()).collect();
+        let bundle_id = format!("bundle--{}", uuid_v5(&Self::SCO_NAMESPACE, object_ids.join(",").as_bytes()));
+
+        serde_json::to_string_pretty(&serde_json::json!({
+            "type": "bundle",
+            "id": bundle_id,
+            "objects": objects,
+        }))
+    }
+}
+
+#[cfg(feature = "stix")]
+impl OutputFormatter<UrlAnalysis> for StixFormatter {
+    type Error = serde_json::Error;
+
+    fn format(&self, analysis: &UrlAnalysis) -> Result<String, Self::Error> {
+        self.format(&vec![analysis.clone()])
+    }
+}
+
+/// Graphviz DOT formatter - renders an analysis and its embedded URLs as a directed graph,
+/// labelling each edge with the query parameter (or `path[n]` segment) that carried the nested URL
+pub struct DotFormatter;
+
+impl DotFormatter {
+    /// Quotes a label for DOT, escaping backslashes, quotes and line breaks
+    fn escape(label: &str) -> String {
+        let escaped = label
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+            .replace('\r', "");
+        format!("\"{}\"", escaped)
+    }
+
+    /// Emits the node for `analysis` and its subtree, returning the node's id
+    fn write_node(analysis: &UrlAnalysis, next_id: &mut usize, lines: &mut Vec<String>) -> String {
+        let id = format!("n{}", next_id);
+        *next_id += 1;
+
+        let host = analysis.url_components.host.as_deref().unwrap_or("");
+        lines.push(format!("  {} [label={}];", id, Self::escape(&format!("{}\n{}", host, analysis.original_url))));
+
+        for embedded in &analysis.embedded_urls {
+            let child = Self::write_node(embedded, next_id, lines);
+            lines.push(format!("  {} -> {} [label={}];", id, child, Self::escape(&embedded_source_label(embedded))));
+        }
+
+        id
+    }
+}
+
+impl OutputFormatter<UrlAnalysis> for DotFormatter {
+    type Error = std::fmt::Error;
+
+    fn format(&self, analysis: &UrlAnalysis) -> Result<String, Self::Error> {
+        let mut lines = vec!["digraph redirects {".to_string(), "  node [shape=box];".to_string()];
+        Self::write_node(analysis, &mut 0, &mut lines);
+        lines.push("}".to_string());
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Where an embedded URL was found: its query parameter, `path[n]`, or empty for the root
+fn embedded_source_label(analysis: &UrlAnalysis) -> String {
+    match (&analysis.source_param, analysis.source_segment) {
+        (Some(param), _) => param.clone(),
+        (None, Some(segment)) => format!("path[{}]", segment),
+        (None, None) => String::new(),
+    }
+}
+
+/// Plain-text tree formatter - the analysis and its embedded URLs as an indented tree with
+/// `├─`/`└─` connectors, one `scheme host (registrable domain)` node per line, for eyeballing
+/// redirect chains in a terminal
+pub struct TreeFormatter;
+
+impl TreeFormatter {
+    fn node_label(analysis: &UrlAnalysis) -> String {
+        let url = &analysis.url_components;
+        let mut label = format!(
+            "{} {} ({})",
+            url.scheme,
+            url.host.as_deref().filter(|host| !host.is_empty()).unwrap_or("-"),
+            analysis.tld_components.registrable_domain().as_deref().unwrap_or("-"),
+        );
+        let source = embedded_source_label(analysis);
+        if !source.is_empty() {
+            label.push_str(&format!(" via {}", source));
+        }
+        if analysis.cycle_detected && analysis.embedded_urls.is_empty() {
+            label.push_str(" [cycle]");
+        }
+        label
+    }
+
+    fn write_children(analysis: &UrlAnalysis, prefix: &str, lines: &mut Vec<String>) {
+        let count = analysis.embedded_urls.len();
+        for (index, embedded) in analysis.embedded_urls.iter().enumerate() {
+            let last = index + 1 == count;
+            lines.push(format!("{}{}{}", prefix, if last { "└─ " } else { "├─ " }, Self::node_label(embedded)));
+            let child_prefix = format!("{}{}", prefix, if last { "   " } else { "│  " });
+            Self::write_children(embedded, &child_prefix, lines);
+        }
+    }
+}
+
+impl OutputFormatter<UrlAnalysis> for TreeFormatter {
+    type Error = std::fmt::Error;
+
+    fn format(&self, analysis: &UrlAnalysis) -> Result<String, Self::Error> {
+        let mut lines = vec![Self::node_label(analysis)];
+        Self::write_children(analysis, "", &mut lines);
+        Ok(lines.join("\n"))
+    }
+}
+
+// ===== SINKS =====
+
+/// Schema created by `SqliteSink`; child rows are deleted with their URL
+#[cfg(feature = "sqlite")]
+const SQLITE_SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+    CREATE TABLE IF NOT EXISTS urls (
+        id INTEGER PRIMARY KEY,
+        original_url TEXT NOT NULL,
+        scheme TEXT NOT NULL,
+        username TEXT NOT NULL,
+        password TEXT,
+        host TEXT,
+        port INTEGER,
+        path TEXT NOT NULL,
+        query TEXT,
+        fragment TEXT,
+        subdomain TEXT,
+        domain TEXT,
+        suffix TEXT
+    );
+    CREATE TABLE IF NOT EXISTS query_params (
+        url_id INTEGER NOT NULL REFERENCES urls(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS path_segments (
+        url_id INTEGER NOT NULL REFERENCES urls(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        segment TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS urls_host ON urls(host);
+    CREATE INDEX IF NOT EXISTS urls_suffix ON urls(suffix);
+";
+
+/// Writes analyses into SQLite tables (`urls`, `query_params`, `path_segments`) for ad-hoc SQL
+#[cfg(feature = "sqlite")]
+pub struct SqliteSink {
+    connection: rusqlite::Connection,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteSink {
+    /// Opens (or creates) a database file and ensures the schema exists
+    pub fn open(path: &std::path::Path) -> rusqlite::Result<Self> {
+        Self::from_connection(rusqlite::Connection::open(path)?)
+    }
+    
+    /// Creates a throwaway in-memory database
+    pub fn in_memory() -> rusqlite::Result<Self> {
+        Self::from_connection(rusqlite::Connection::open_in_memory()?)
+    }
+    
+    pub fn from_connection(connection: rusqlite::Connection) -> rusqlite::Result<Self> {
+        connection.execute_batch(SQLITE_SCHEMA)?;
+        Ok(Self { connection })
+    }
+    
+    /// Inserts one analysis and returns its `urls.id`
+    pub fn insert(&mut self, analysis: &UrlAnalysis) -> rusqlite::Result<i64> {
+        let transaction = self.connection.transaction()?;
+        let id = Self::insert_rows(&transaction, analysis)?;
+        transaction.commit()?;
+        Ok(id)
+    }
+    
+    /// Inserts a batch in a single transaction; nothing is written if any insert fails
+    pub fn insert_batch(&mut self, analyses: &[UrlAnalysis]) -> rusqlite::Result<usize> {
+        let transaction = self.connection.transaction()?;
+        for analysis in analyses {
+            Self::insert_rows(&transaction, analysis)?;
+        }
+        transaction.commit()?;
+        Ok(analyses.len())
+    }
+    
+    /// The underlying connection, for running queries
+    pub fn connection(&self) -> &rusqlite::Connection {
+        &self.connection
+    }
+    
+    fn insert_rows(connection: &rusqlite::Connection, analysis: &UrlAnalysis) -> rusqlite::Result<i64> {
+        let url = &analysis.url_components;
+        let tld = &analysis.tld_components;
+        
+        connection.execute(
+            "INSERT INTO urls (original_url, scheme, username, password, host, port, path, query, fragment, subdomain, domain, suffix)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            rusqlite::params![
+                analysis.original_url, url.scheme, url.username, url.password, url.host, url.port,
+                url.path, url.query, url.fragment, tld.subdomain, tld.domain, tld.suffix,
+            ],
+        )?;
+        let url_id = connection.last_insert_rowid();
+        
+        let mut insert_param = connection.prepare_cached(
+            "INSERT INTO query_params (url_id, position, key, value) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for (position, (key, value)) in url.query_params.iter().enumerate() {
+            insert_param.execute(rusqlite::params![url_id, position as i64, key, value])?;
+        }
+        
+        let mut insert_segment = connection.prepare_cached(
+            "INSERT INTO path_segments (url_id, position, segment) VALUES (?1, ?2, ?3)",
+        )?;
+        for (position, segment) in url.path_segments.iter().enumerate() {
+            insert_segment.execute(rusqlite::params![url_id, position as i64, segment])?;
+        }
+        
+        Ok(url_id)
+    }
+}
+
+// ===== UTILITY FUNCTIONS =====
+
+/// Extracts hosts from a query parameter value carrying JSON, e.g. `{"url":"https://evil.com"}`.
+/// The value is percent-decoded first; anything that is not JSON yields no hosts.
+pub fn extract_json_param_hosts(value: &str) -> Vec<String> {
+    let decoded = percent_encoding::percent_decode_str(value).decode_utf8_lossy();
+    let trimmed = decoded.trim();
+    if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return Vec::new();
+    }
+
+    let mut hosts = Vec::new();
+    if let Ok(json) = serde_json::from_str::<serde_json::Value>(trimmed) {
+        collect_json_hosts(&json, &mut hosts);
+    }
+    hosts
+}
+
+/// Walks a JSON value, collecting hosts from every string that is a URL or a bare domain
+fn collect_json_hosts(value: &serde_json::Value, hosts: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(text) => {
+            let host = match Url::parse(text) {
+                Ok(url) => url.host_str().map(|h| h.to_ascii_lowercase()),
+                Err(_) if looks_like_domain(text) => Some(text.to_ascii_lowercase()),
+                Err(_) => None,
+            };
+            if let Some(host) = host {
+                if !hosts.contains(&host) {
+                    hosts.push(host);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_json_hosts(item, hosts);
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for item in fields.values() {
+                collect_json_hosts(item, hosts);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns true for strings shaped like a dotted hostname with an alphabetic final label
+fn looks_like_domain(text: &str) -> bool {
+    let labels: Vec<&str> = text.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        && labels.last().is_some_and(|tld| tld.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+/// Schemes the WHATWG URL standard calls "special", for which `\\` is read as `/`
+const SPECIAL_SCHEMES: &[&str] = &["http", "https", "ws", "wss", "ftp", "file"];
+
+/// Returns true when a backslash appears before the query/fragment, where WHATWG and
+/// RFC 3986 parsers disagree about its meaning. Raw UNC paths are handled separately.
+fn contains_structural_backslash(raw: &str) -> bool {
+    let raw = raw.trim();
+    if UncPath::to_file_url(raw).is_some() {
+        return false;
+    }
+    let end = raw.find(['?', '#']).unwrap_or(raw.len());
+    raw[..end].contains('\\')
+}
+
+/// Percent-encodes backslashes before the query of a special-scheme URL so they stay literal
+fn encode_backslashes(input: &str) -> String {
+    let is_special = input.split_once(':')
+        .is_some_and(|(scheme, _)| SPECIAL_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str()));
+    if !is_special {
+        return input.to_string();
+    }
+
+    let end = input.find(['?', '#']).unwrap_or(input.len());
+    format!("{}{}", input[..end].replace('\\', "%5C"), &input[end..])
+}
+
+/// Applies the UTS #46 transitional mappings to the host of `input`: the four deviation
+/// characters are the only difference from the nontransitional processing the url crate does
+fn map_idna_deviations(input: &str) -> String {
+    let Some(authority_start) = input.find("://").map(|index| index + 3) else {
+        return input.to_string();
+    };
+    let authority_end = input[authority_start..].find(['/', '\\', '?', '#'])
+        .map_or(input.len(), |index| authority_start + index);
+    let host_start = input[authority_start..authority_end].rfind('@')
+        .map_or(authority_start, |index| authority_start + index + 1);
+
+    let host: String = input[host_start..authority_end].chars()
+        .filter(|c| !matches!(c, '\u{200C}' | '\u{200D}'))
+        .map(|c| match c {
+            'ß' | 'ẞ' => "ss".to_string(),
+            'ς' => "σ".to_string(),
+            other => other.to_string(),
+        })
+        .collect();
+    format!("{}{}{}", &input[..host_start], host, &input[authority_end..])
+}
+
+/// Returns the authority (`userinfo@host:port`) exactly as written in the raw input
+fn raw_authority(raw: &str) -> Option<&str> {
+    let (_, rest) = raw.trim().split_once("://")?;
+    let end = rest.find(['/', '\\', '?', '#']).unwrap_or(rest.len());
+    Some(&rest[..end])
+}
+
+/// Returns the host text of a raw authority, without userinfo or port
+fn raw_host(authority: &str) -> &str {
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host_port)| host_port);
+    match raw_port(host_port) {
+        Some(port) => &host_port[..host_port.len() - port.len() - 1],
+        None => host_port,
+    }
+}
+
+/// Decodes an IPv4 address written in a non-canonical form, following the WHATWG IPv4
+/// parser: each dot-separated part may be hex (`0x`), octal (leading `0`) or decimal, and
+/// the last part fills the remaining bytes (`127.1`, `2130706433`). Returns `None` for
+/// canonical dotted-decimal input and for anything that isn't an IPv4 address.
+pub fn decode_obfuscated_ipv4(host: &str) -> Option<std::net::Ipv4Addr> {
+    if host.parse::<std::net::Ipv4Addr>().is_ok() {
+        return None;
+    }
+
+    let trimmed = host.strip_suffix('.').unwrap_or(host);
+    let parts: Vec<&str> = trimmed.split('.').collect();
+    if parts.is_empty() || parts.len() > 4 {
+        return None;
+    }
+
+    let mut numbers = Vec::with_capacity(parts.len());
+    for part in &parts {
+        let lower = part.to_ascii_lowercase();
+        let number = if let Some(hex) = lower.strip_prefix("0x") {
+            if hex.is_empty() { 0 } else { u64::from_str_radix(hex, 16).ok()? }
+        } else if lower.len() > 1 && lower.starts_with('0') {
+            u64::from_str_radix(&lower[1..], 8).ok()?
+        } else if !lower.is_empty() && lower.bytes().all(|b| b.is_ascii_digit()) {
+            lower.parse::<u64>().ok()?
+        } else {
+            return None;
+        };
+        numbers.push(number);
+    }
+
+    let (last, leading) = numbers.split_last()?;
+    if leading.iter().any(|&n| n > 255) || *last >= 1u64 << (8 * (5 - numbers.len())) {
+        return None;
+    }
+
+    let address = leading.iter()
+        .enumerate()
+        .fold(*last, |acc, (i, &n)| acc + (n << (8 * (3 - i))));
+    Some(std::net::Ipv4Addr::from(address as u32))
+}
+
+/// Returns the port text of a raw authority, if one was written
+fn raw_port(authority: &str) -> Option<&str> {
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host_port)| host_port);
+    let after_host = match host_port.strip_prefix('[') {
+        Some(rest) => &rest[rest.find(']')? + 1..],
+        None => host_port,
+    };
+    after_host.rsplit_once(':').map(|(_, port)| port)
+}
+
+/// Detects the `trusted.com@evil.com` phishing shape in a raw authority
+fn is_ambiguous_authority(authority: &str) -> bool {
+    let at_signs = authority.matches('@').count();
+    if at_signs > 1 {
+        return true;
+    }
+
+    match authority.rsplit_once('@') {
+        Some((userinfo, _)) => {
+            let user = userinfo.split(':').next().unwrap_or(userinfo);
+            looks_like_domain(user)
+        }
+        None => false,
+    }
+}
+
+/// Registered default port of a scheme. The url crate omits the port when it matches the
+/// default for `http`, `https`, `ws`, `wss` and `ftp`; the rest keep an explicit port as written.
+fn well_known_port(scheme: &str) -> Option<u16> {
+    match scheme.to_ascii_lowercase().as_str() {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        "ftps" => Some(990),
+        "sftp" | "ssh" | "scp" => Some(22),
+        "telnet" => Some(23),
+        "smtp" => Some(25),
+        "gopher" => Some(70),
+        "pop3" => Some(110),
+        "nntp" => Some(119),
+        "imap" => Some(143),
+        "ldap" => Some(389),
+        "smtps" => Some(465),
+        "rtsp" => Some(554),
+        "ldaps" => Some(636),
+        "imaps" => Some(993),
+        "pop3s" => Some(995),
+        "mysql" => Some(3306),
+        "postgres" | "postgresql" => Some(5432),
+        "sip" => Some(5060),
+        "amqp" => Some(5672),
+        "amqps" => Some(5671),
+        "redis" | "rediss" => Some(6379),
+        "irc" => Some(6667),
+        "git" => Some(9418),
+        "mongodb" => Some(27017),
+        _ => None,
+    }
+}
+
+/// Decodes punycode labels to Unicode. Labels that fail to decode (bad punycode, disallowed
+/// mixes) are left in their ASCII form rather than erroring, so this never fails.
+fn host_to_unicode(host: &str) -> String {
+    host.split('.')
+        .map(|label| match idna::domain_to_unicode(label) {
+            (unicode, Ok(())) => unicode,
+            (_, Err(_)) => label.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Punycode form of a dotted name, converting each label on its own and keeping any label
+/// `idna` rejects as it was
+fn host_to_ascii(host: &str) -> String {
+    host.split('.')
+        .map(|label| idna::domain_to_ascii(label).unwrap_or_else(|_| label.to_string()))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Sorts `params` by key, then value, and form-encodes them; `None` when there are none
+fn sorted_form_query(params: &[(String, String)]) -> Option<String> {
+    if params.is_empty() {
+        return None;
+    }
+
+    let mut params = params.to_vec();
+    params.sort();
+    Some(url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(params)
+        .finish())
+}
+
+/// Removes exactly one layer of percent-encoding; invalid UTF-8 is replaced with U+FFFD
+pub fn percent_decode_once(value: &str) -> String {
+    percent_encoding::percent_decode_str(value).decode_utf8_lossy().into_owned()
+}
+
+/// Levenshtein edit distance between two strings, counted in characters
+pub fn levenshtein_distance(a: &str, b: &str) -> u32 {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<u32> = (0..=b.len() as u32).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i as u32 + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + u32::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// 64-bit FNV-1a hash; fixed constants keep results stable across platforms and releases
+fn fnv1a_64(input: &str) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    input.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Name-based UUID (version 5) as defined by RFC 4122
+#[cfg(feature = "stix")]
+fn uuid_v5(namespace: &[u8; 16], name: &[u8]) -> String {
+    let mut input = namespace.to_vec();
+    input.extend_from_slice(name);
+    let digest = sha1(&input);
+
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// SHA-1 digest, needed only to derive UUIDv5 identifiers (not used for security)
+#[cfg(feature = "stix")]
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476, 0xc3d2_e1f0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut words = [0u32; 80];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            words[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5a82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ed9_eba1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (slot, value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *slot = slot.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 20];
+    for (i, word) in state.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+/// Serde helper for omitting `false` flags from output
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct UrlComponents {
-    pub scheme: String,
-    pub username: String,
-    pub password: Option<String>,
-    pub host: Option<String>,
-    pub port: Option<u16>,
-    pub path: String,
-    pub query: Option<String>,
-    pub fragment: Option<String>,
-    pub query_params: Vec<(String, String)>,
-    pub path_segments: Vec<String>,
+/// Query parameters whose values are secrets, redacted by `UrlComponents::redact_sensitive_params`
+pub const SENSITIVE_PARAMS: &[&str] = &[
+    "token", "access_token", "refresh_token", "id_token", "auth", "sessionid", "session_id",
+    "sid", "api_key", "apikey", "sig", "signature", "password", "secret", "client_secret",
+];
+
+/// Click identifiers that never change what a page serves
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_ga", "_gl"];
+
+/// Returns true for analytics/campaign parameters (`utm_*` and known click IDs)
+pub fn is_tracking_param(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key.as_str())
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct TldComponents {
-    pub domain: Option<String>,
-    pub subdomain: Option<String>,
-    pub suffix: Option<String>,
+/// Uppercases the hex digits of every percent-escape (`%2f` -> `%2F`), which RFC 3986
+/// defines as equivalent. Used by every comparison and key API so encoders that differ
+/// only in case don't produce false differences.
+pub fn normalize_percent_case(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut normalized = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(pos) = rest.find('%') {
+        let escape_start = s.len() - rest.len() + pos;
+        normalized.push_str(&rest[..pos]);
+        let digits = bytes.get(escape_start + 1..escape_start + 3);
+        match digits {
+            Some(digits) if digits.iter().all(u8::is_ascii_hexdigit) => {
+                normalized.push('%');
+                normalized.push_str(&rest[pos + 1..pos + 3].to_ascii_uppercase());
+                rest = &rest[pos + 3..];
+            }
+            _ => {
+                normalized.push('%');
+                rest = &rest[pos + 1..];
+            }
+        }
+    }
+    normalized.push_str(rest);
+
+    normalized
 }
 
-// ===== URL PARSER (Single Responsibility) =====
+/// Everything except RFC 3986 unreserved characters (`A-Z a-z 0-9 - . _ ~`)
+const QUERY_COMPONENT_ENCODE_SET: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-').remove(b'.').remove(b'_').remove(b'~');
 
-/// Handles pure URL parsing without TLD extraction
-pub struct UrlParser;
+/// Like `QUERY_COMPONENT_ENCODE_SET`, but keeps the sub-delimiters, `:` and `@` that are
+/// literal inside a path segment
+const PATH_COMPONENT_ENCODE_SET: &percent_encoding::AsciiSet = &QUERY_COMPONENT_ENCODE_SET
+    .remove(b'!').remove(b'$').remove(b'&').remove(b'\'').remove(b'(').remove(b')')
+    .remove(b'*').remove(b'+').remove(b',').remove(b';').remove(b'=').remove(b':').remove(b'@');
 
-impl UrlParser {
-    pub fn new() -> Self {
-        Self
+/// Percent-encodes `s` for use as a single query key or value. Spaces become `%20`, and
+/// `&`, `=`, `+` and `#` are always escaped; non-ASCII is encoded as UTF-8 bytes.
+pub fn encode_query_component(s: &str) -> String {
+    percent_encoding::utf8_percent_encode(s, QUERY_COMPONENT_ENCODE_SET).to_string()
+}
+
+/// Percent-encodes `s` for use as a single path segment, so `/`, `?` and `#` are escaped
+pub fn encode_path_component(s: &str) -> String {
+    percent_encoding::utf8_percent_encode(s, PATH_COMPONENT_ENCODE_SET).to_string()
+}
+
+/// Splits CSV text into records per RFC 4180: quoted fields may contain commas, newlines
+/// and doubled `""` quotes. Blank lines produce no record.
+fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|value| !value.is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            _ => field.push(c),
+        }
     }
-    
-    pub fn parse(&self, url_str: &str) -> Result<(Url, UrlComponents), Box<dyn std::error::Error>> {
-        let parsed_url = Url::parse(url_str)?;
-        
-        let components = UrlComponents {
-            scheme: parsed_url.scheme().to_string(),
-            username: parsed_url.username().to_string(),
-            password: parsed_url.password().map(|s| s.to_string()),
-            host: parsed_url.host_str().map(|s| s.to_string()),
-            port: parsed_url.port(),
-            path: parsed_url.path().to_string(),
-            query: parsed_url.query().map(|s| s.to_string()),
-            fragment: parsed_url.fragment().map(|s| s.to_string()),
-            query_params: parsed_url.query_pairs()
-                .map(|(k, v)| (k.to_string(), v.to_string()))
-                .collect(),
-            path_segments: parsed_url.path_segments()
-                .map(|segments| segments.filter(|s| !s.is_empty()).map(|s| s.to_string()).collect())
-                .unwrap_or_default(),
-        };
-        
-        Ok((parsed_url, components))
+    record.push(field);
+    if record.iter().any(|value| !value.is_empty()) {
+        records.push(record);
     }
+
+    records
 }
 
-impl Default for UrlParser {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Cuts `line` at the first comment `prefix` that follows whitespace
+fn strip_inline_comment<'a>(line: &'a str, prefix: &str) -> &'a str {
+    line.char_indices()
+        .find(|&(i, c)| c.is_whitespace() && line[i..].trim_start().starts_with(prefix))
+        .map_or(line, |(i, _)| &line[..i])
 }
 
-// ===== TLD ANALYZER (Single Responsibility) =====
+/// Public suffixes Google serves search (and the AMP viewer) under
+const GOOGLE_SUFFIXES: &[&str] = &[
+    "com", "ad", "ae", "al", "am", "as", "at", "az", "ba", "be", "bf", "bg", "bi", "bj", "bs", "bt", "by",
+    "ca", "cd", "cf", "cg", "ch", "ci", "cl", "cm", "cn", "cv", "cz", "de", "dj", "dk", "dm", "dz", "ee",
+    "es", "fi", "fm", "fr", "ga", "ge", "gg", "gl", "gm", "gr", "gy", "hn", "hr", "ht", "hu", "ie", "im",
+    "iq", "is", "it", "je", "jo", "kg", "ki", "kz", "la", "li", "lk", "lt", "lu", "lv", "md", "me", "mg",
+    "mk", "ml", "mn", "mu", "mv", "mw", "ne", "nl", "no", "nr", "nu", "pl", "pn", "ps", "pt", "ro", "rs",
+    "ru", "rw", "sc", "se", "sh", "si", "sk", "sm", "sn", "so", "sr", "st", "td", "tg", "tl", "tm", "tn",
+    "to", "tt", "vg", "vu", "ws",
+    "co.ao", "co.bw", "co.ck", "co.cr", "co.id", "co.il", "co.in", "co.jp", "co.ke", "co.kr", "co.ls",
+    "co.ma", "co.mz", "co.nz", "co.th", "co.tz", "co.ug", "co.uk", "co.uz", "co.ve", "co.vi", "co.za",
+    "co.zm", "co.zw",
+    "com.af", "com.ag", "com.ar", "com.au", "com.bd", "com.bh", "com.bn", "com.bo", "com.br", "com.bz",
+    "com.co", "com.cu", "com.cy", "com.do", "com.ec", "com.eg", "com.et", "com.fj", "com.gh", "com.gi",
+    "com.gt", "com.hk", "com.jm", "com.kh", "com.kw", "com.lb", "com.ly", "com.mm", "com.mt", "com.mx",
+    "com.my", "com.na", "com.ng", "com.ni", "com.np", "com.om", "com.pa", "com.pe", "com.pg", "com.ph",
+    "com.pk", "com.pr", "com.py", "com.qa", "com.sa", "com.sb", "com.sg", "com.sl", "com.sv", "com.tj",
+    "com.tr", "com.tw", "com.ua", "com.uy", "com.vc", "com.vn",
+];
 
-/// Handles TLD extraction separately from URL parsing
-pub struct TldAnalyzer {
-    extractor: TldExtractor,
-}
+/// Decodes an AMP cache subdomain back to the publisher host (`www-example-com` ->
+/// `www.example.com`, `my--site-com` -> `my-site.com`)
+fn decode_amp_subdomain(encoded: &str) -> String {
+    let mut host = String::with_capacity(encoded.len());
+    let mut chars = encoded.chars().peekable();
 
-impl TldAnalyzer {
-    pub fn new() -> Self {
-        Self {
-            extractor: TldExtractor::new(TldOption::default()),
+    while let Some(c) = chars.next() {
+        match c {
+            '-' if chars.peek() == Some(&'-') => {
+                host.push('-');
+                chars.next();
+            }
+            '-' => host.push('.'),
+            _ => host.push(c),
         }
     }
-    
-    pub fn extract(&self, host: &str) -> Result<TldComponents, Box<dyn std::error::Error>> {
-        let extracted = self.extractor.extract(host)?;
-        
-        Ok(TldComponents {
-            domain: extracted.domain.filter(|s| !s.is_empty()),
-            subdomain: extracted.subdomain.filter(|s| !s.is_empty()),
-            suffix: extracted.suffix.filter(|s| !s.is_empty()),
-        })
-    }
+
+    host
 }
 
-impl Default for TldAnalyzer {
-    fn default() -> Self {
-        Self::new()
+/// Replaces the five predefined XML entities; `&amp;` goes last so `&amp;lt;` stays `&lt;`
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Shannon entropy of `text` in bits per character
+fn shannon_entropy(text: &str) -> f64 {
+    let mut counts = std::collections::HashMap::new();
+    for c in text.chars() {
+        *counts.entry(c).or_insert(0usize) += 1;
     }
+    let len = text.chars().count() as f64;
+
+    counts.values()
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
 }
 
-// ===== COMPOSITE ANALYZER =====
+/// Returns true when `host` is an IPv4 address or a (bracketed) IPv6 literal
+fn is_ip_host(host: &str) -> bool {
+    let unbracketed = host.trim_start_matches('[').trim_end_matches(']');
+    unbracketed.parse::<std::net::IpAddr>().is_ok()
+}
 
-/// Combines URL parsing and TLD extraction
-pub struct ComprehensiveUrlAnalyzer {
-    url_parser: UrlParser,
-    tld_analyzer: TldAnalyzer,
+/// Returns true when the rightmost label of `host` is all digits (`example.123`) but the
+/// host is not an IPv4 address in any form the WHATWG parser accepts
+fn has_numeric_final_label(host: &str) -> bool {
+    let host = host.strip_suffix('.').unwrap_or(host);
+    let numeric = host.rsplit('.').next()
+        .is_some_and(|label| !label.is_empty() && label.bytes().all(|b| b.is_ascii_digit()));
+    numeric && !is_ip_host(host) && decode_obfuscated_ipv4(host).is_none()
 }
 
-impl ComprehensiveUrlAnalyzer {
-    pub fn new() -> Self {
-        Self {
-            url_parser: UrlParser::new(),
-            tld_analyzer: TldAnalyzer::new(),
-        }
-    }
+/// Convenience function to analyze a single URL
+pub fn analyze_url(url: &str) -> Result<UrlAnalysis, UrlAnalysisError> {
+    let analyzer = ComprehensiveUrlAnalyzer::new();
+    analyzer.analyze(url)
 }
 
-impl Default for ComprehensiveUrlAnalyzer {
-    fn default() -> Self {
-        Self::new()
+/// Where in a URL a domain was discovered
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum EmbeddedSource {
+    /// The URL's own host
+    Host,
+    /// The value of the named query parameter
+    QueryParam(String),
+    /// The path segment at this index
+    PathSegment(usize),
+    /// The fragment, or a value in a `key=value` fragment
+    Fragment,
+}
+
+/// What kind of indicator a `Discovery` is
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiscoveryKind {
+    /// The URL's own hostname
+    Host,
+    /// A bare domain name, or the registrable domain of the URL's host
+    Domain,
+    /// A full embedded URL
+    Url,
+    /// An IPv4 or IPv6 address
+    Ip,
+    /// An email address
+    Email,
+}
+
+/// A single indicator found in a URL, as returned by `UrlAnalysis::discoveries_ordered`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Discovery {
+    pub value: String,
+    pub kind: DiscoveryKind,
+    pub source: EmbeddedSource,
+}
+
+/// Classifies a query value, path segment or fragment as an indicator, if it is one
+fn classify_discovery(value: &str) -> Option<DiscoveryKind> {
+    if value.contains("://") && Url::parse(value).is_ok_and(|url| url.host_str().is_some()) {
+        return Some(DiscoveryKind::Url);
     }
+    if is_ip_host(value) {
+        return Some(DiscoveryKind::Ip);
+    }
+    if let Some((local, domain)) = value.split_once('@') {
+        let is_email = !local.is_empty() && !local.contains(['/', ':']) && looks_like_domain(domain);
+        return is_email.then_some(DiscoveryKind::Email);
+    }
+    looks_like_domain(value).then_some(DiscoveryKind::Domain)
 }
 
-impl UrlAnalyzer for ComprehensiveUrlAnalyzer {
-    type Output = UrlAnalysis;
-    type Error = Box<dyn std::error::Error>;
-    
-    fn analyze(&self, url_str: &str) -> Result<Self::Output, Self::Error> {
-        let (parsed_url, url_components) = self.url_parser.parse(url_str)?;
-        
-        let tld_components = if let Some(host) = parsed_url.host_str() {
-            self.tld_analyzer.extract(host)?
-        } else {
-            TldComponents {
-                domain: None,
-                subdomain: None,
-                suffix: None,
+/// Every domain `WhoisFormatter` would print for `analysis`, sorted and deduplicated: the
+/// registrable domain of the host, of URLs and domain-like values in query parameters, and of
+/// domain-like path segments (full hosts instead with `include_subdomains`). Loads the suffix
+/// list on every call; use `TldAnalyzer::extract_all_domains` for batches.
+pub fn extract_all_domains(analysis: &UrlAnalysis, include_subdomains: bool) -> Vec<String> {
+    TldAnalyzer::new().extract_all_domains(analysis, include_subdomains)
+}
+
+/// Returns the distinct schemes used across a batch, e.g. to spot non-https entries in a feed
+pub fn unique_schemes(analyses: &[UrlAnalysis]) -> std::collections::BTreeSet<String> {
+    analyses.iter()
+        .map(|analysis| analysis.url_components.scheme.clone())
+        .collect()
+}
+
+/// Convenience function to analyze a single URL and collect non-fatal warnings
+pub fn analyze_with_warnings(url: &str) -> Result<(UrlAnalysis, Vec<Warning>), UrlAnalysisError> {
+    let analyzer = ComprehensiveUrlAnalyzer::new();
+    analyzer.analyze_with_warnings(url)
+}
+
+/// Convenience function to analyze multiple URLs; each failure carries a typed
+/// `UrlAnalysisError` so batch reports can count failures by reason
+pub fn analyze_urls(urls: &[&str]) -> Vec<Result<UrlAnalysis, UrlAnalysisError>> {
+    let analyzer = ComprehensiveUrlAnalyzer::new();
+    urls.iter().map(|url| analyzer.analyze(url)).collect()
+}
+
+/// One data row of a CSV processed by `analyze_csv_column`
+#[derive(Debug)]
+pub struct CsvRowAnalysis {
+    /// 1-based data row number (the header row is not counted)
+    pub row_number: usize,
+    /// Every column of the row as `(header, value)`, so metadata can be carried through
+    pub row: Vec<(String, String)>,
+    pub result: Result<UrlAnalysis, UrlAnalysisError>,
+}
+
+/// Reads a CSV file with a header row and analyzes the URL in the column named `column_name`.
+/// A missing header fails the whole call with `UrlAnalysisError::MissingColumn`; a row
+/// without a value in that column gets a per-row `UrlAnalysisError::MissingValue`. Blank lines are skipped.
+pub fn analyze_csv_column(path: &std::path::Path, column_name: &str) -> Result<Vec<CsvRowAnalysis>, UrlAnalysisError> {
+    let contents = std::fs::read_to_string(path).map_err(|error| UrlAnalysisError::Io(error.to_string()))?;
+    let mut records = parse_csv(&contents).into_iter();
+
+    let missing_column = || UrlAnalysisError::MissingColumn(column_name.to_string());
+    let headers = records.next().ok_or_else(missing_column)?;
+    let column = headers.iter()
+        .position(|header| header.trim() == column_name)
+        .ok_or_else(missing_column)?;
+
+    let analyzer = ComprehensiveUrlAnalyzer::new();
+    let rows = records.enumerate()
+        .map(|(index, fields)| {
+            let result = match fields.get(column).map(|url| url.trim()) {
+                Some(url) if !url.is_empty() => analyzer.analyze(url),
+                _ => Err(UrlAnalysisError::MissingValue { row: index + 1, column: column_name.to_string() }),
+            };
+            CsvRowAnalysis {
+                row_number: index + 1,
+                row: headers.iter().cloned().zip(fields).collect(),
+                result,
             }
-        };
-        
-        Ok(UrlAnalysis {
-            original_url: url_str.to_string(),
-            url_components,
-            tld_components,
         })
-    }
+        .collect();
+
+    Ok(rows)
 }
 
-// ===== OUTPUT FORMATTERS =====
+/// Browser whose history database `analyze_browser_history` reads
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    /// Chromium-based browsers (Chrome, Edge, Brave): the `History` file, table `urls`
+    Chrome,
+    /// Firefox: the `places.sqlite` file, table `moz_places`
+    Firefox,
+}
 
-/// JSON formatter
-pub struct JsonFormatter;
+#[cfg(feature = "sqlite")]
+impl Browser {
+    /// Query returning `url`, visit count and last visit time in the browser's own units
+    fn history_query(self) -> &'static str {
+        match self {
+            Browser::Chrome => "SELECT url, visit_count, last_visit_time FROM urls ORDER BY id",
+            Browser::Firefox => "SELECT url, visit_count, last_visit_date FROM moz_places ORDER BY id",
+        }
+    }
 
-impl<T> OutputFormatter<T> for JsonFormatter 
-where 
-    T: Serialize 
-{
-    type Error = serde_json::Error;
-    
-    fn format(&self, data: &T) -> Result<String, Self::Error> {
-        serde_json::to_string_pretty(data)
+    /// Converts a stored last-visit value to microseconds since the Unix epoch. Chrome counts
+    /// from 1601-01-01 and stores 0 for never; Firefox already uses the Unix epoch or NULL.
+    fn unix_micros(self, raw: Option<i64>) -> Option<i64> {
+        const CHROME_EPOCH_OFFSET_MICROS: i64 = 11_644_473_600_000_000;
+        match self {
+            Browser::Chrome => raw.filter(|&time| time > 0).map(|time| time - CHROME_EPOCH_OFFSET_MICROS),
+            Browser::Firefox => raw,
+        }
     }
 }
 
-/// Compact JSON formatter  
-pub struct CompactJsonFormatter;
+/// One row of a browser history database processed by `analyze_browser_history`
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub visit_count: Option<u64>,
+    /// Last visit in microseconds since the Unix epoch, whatever the browser stores
+    pub last_visit_unix_micros: Option<i64>,
+    pub result: Result<UrlAnalysis, UrlAnalysisError>,
+}
 
-impl<T> OutputFormatter<T> for CompactJsonFormatter 
-where 
-    T: Serialize 
-{
-    type Error = serde_json::Error;
-    
-    fn format(&self, data: &T) -> Result<String, Self::Error> {
-        serde_json::to_string(data)
-    }
+/// Reads every URL from a Chrome or Firefox history database and analyzes it, in table order.
+/// The file is opened read-only; browsers lock their live database, so point this at a copy.
+/// A missing or foreign table fails the whole call with `UrlAnalysisError::Io`; an
+/// unparsable URL gets a per-row error.
+#[cfg(feature = "sqlite")]
+pub fn analyze_browser_history(db_path: &std::path::Path, browser: Browser) -> Result<Vec<HistoryEntry>, UrlAnalysisError> {
+    let read_history = || -> rusqlite::Result<Vec<(String, Option<i64>, Option<i64>)>> {
+        let connection = rusqlite::Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut statement = connection.prepare(browser.history_query())?;
+        let rows = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(rows)
+    };
+    let rows = read_history().map_err(|error| UrlAnalysisError::Io(error.to_string()))?;
+
+    let analyzer = ComprehensiveUrlAnalyzer::new();
+    let entries = rows.into_iter()
+        .map(|(url, visit_count, last_visit)| HistoryEntry {
+            result: analyzer.analyze(&url),
+            url,
+            visit_count: visit_count.and_then(|count| u64::try_from(count).ok()),
+            last_visit_unix_micros: browser.unix_micros(last_visit),
+        })
+        .collect();
+
+    Ok(entries)
 }
 
-/// Whois-specific formatter - extracts domains for whois lookup
-pub struct WhoisFormatter {
-    include_subdomains: bool,
+/// Why `analyze_and_filter` dropped a URL
+#[derive(Debug, Clone, PartialEq)]
+pub enum RejectReason {
+    /// `host` (the URL's own or an embedded one) matched blocklist `pattern`
+    Blocklisted { host: String, pattern: String },
+    /// The URL could not be analyzed
+    AnalysisFailed(String),
 }
 
-impl WhoisFormatter {
-    pub fn new() -> Self {
-        Self {
-            include_subdomains: false,
+/// A URL dropped by `analyze_and_filter`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedUrl {
+    pub url: String,
+    pub reason: RejectReason,
+}
+
+/// Analyzes each URL and keeps only those whose host and embedded hosts (query values, path
+/// segments, fragment, and nested embedded URLs at any depth) are all clear of `blocklist`
+pub fn analyze_and_filter(urls: &[&str], blocklist: &DomainMatcher) -> (Vec<UrlAnalysis>, Vec<RejectedUrl>) {
+    let analyzer = ComprehensiveUrlAnalyzer::new();
+    let mut kept = Vec::new();
+    let mut rejected = Vec::new();
+
+    for url in urls {
+        let analysis = match analyzer.analyze(url) {
+            Ok(analysis) => analysis,
+            Err(error) => {
+                rejected.push(RejectedUrl { url: url.to_string(), reason: RejectReason::AnalysisFailed(error.to_string()) });
+                continue;
+            }
+        };
+
+        let mut hosts = Vec::new();
+        analysis.collect_discovered_hosts(&mut hosts);
+        let hosts = hosts.into_iter().chain(analysis.embedded_hosts.iter().cloned());
+        let hit = hosts
+            .filter_map(|host| blocklist.matches(&host).map(|pattern| (host.clone(), pattern.to_string())))
+            .next();
+
+        match hit {
+            Some((host, pattern)) => rejected.push(RejectedUrl {
+                url: url.to_string(),
+                reason: RejectReason::Blocklisted { host, pattern },
+            }),
+            None => kept.push(analysis),
         }
     }
-    
-    pub fn with_subdomains(mut self) -> Self {
-        self.include_subdomains = true;
-        self
-    }
+
+    (kept, rejected)
 }
 
-impl Default for WhoisFormatter {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Analyzes every URL in a single comma- and/or whitespace-delimited string, such as one
+/// quoted CLI argument (`"https://a.com, https://b.com https://c.com"`). Only whitespace and
+/// commas next to whitespace or at the ends of a token separate URLs, so `?ids=1,2` stays intact.
+pub fn analyze_delimited(input: &str) -> Vec<Result<UrlAnalysis, UrlAnalysisError>> {
+    let urls: Vec<&str> = input.split_whitespace()
+        .map(|token| token.trim_matches(','))
+        .filter(|token| !token.is_empty())
+        .collect();
+    analyze_urls(&urls)
 }
 
-impl OutputFormatter<Vec<UrlAnalysis>> for WhoisFormatter {
-    type Error = std::fmt::Error;
-    
-    fn format(&self, analyses: &Vec<UrlAnalysis>) -> Result<String, Self::Error> {
-        let mut domains = std::collections::HashSet::new();
-        
-        for analysis in analyses {
-            // Extract the main domain for whois lookup
-            if let Some(host) = &analysis.url_components.host {
-                if self.include_subdomains {
-                    // Include full domain with subdomains
-                    domains.insert(host.clone());
-                } else {
-                    // Extract just the registrable domain (domain + suffix)
-                    if let (Some(domain), Some(suffix)) = (&analysis.tld_components.domain, &analysis.tld_components.suffix) {
-                        domains.insert(format!("{}.{}", domain, suffix));
-                    } else {
-                        // Fallback to full host if TLD extraction failed
-                        domains.insert(host.clone());
-                    }
+/// Streams cleaned-up URLs from a line-oriented list; see `read_url_lines`
+pub struct UrlLineReader<R> {
+    lines: std::io::Lines<R>,
+    options: ReaderOptions,
+}
+
+impl<R: std::io::BufRead> Iterator for UrlLineReader<R> {
+    type Item = std::io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            let mut entry = line.trim();
+
+            if let Some(prefix) = self.options.comment_prefix.as_deref().filter(|prefix| !prefix.is_empty()) {
+                if entry.starts_with(prefix) {
+                    continue;
                 }
-            }
-            
-            // Also extract domains from embedded URLs in query params
-            for (_, value) in &analysis.url_components.query_params {
-                // First try to parse as a full URL
-                if let Ok(embedded_url) = Url::parse(value) {
-                    if let Some(embedded_host) = embedded_url.host_str() {
-                        if self.include_subdomains {
-                            domains.insert(embedded_host.to_string());
-                        } else {
-                            // Try to extract domain from embedded URL
-                            let analyzer = TldAnalyzer::new();
-                            if let Ok(tld_components) = analyzer.extract(embedded_host) {
-                                if let (Some(domain), Some(suffix)) = (tld_components.domain, tld_components.suffix) {
-                                    domains.insert(format!("{}.{}", domain, suffix));
-                                }
-                            } else {
-                                domains.insert(embedded_host.to_string());
-                            }
-                        }
-                    }
-                } else if value.contains('.') && !value.starts_with('%') {
-                    // If it's not a valid URL but looks like a domain, try to extract it
-                    let analyzer = TldAnalyzer::new();
-                    if let Ok(tld_components) = analyzer.extract(value) {
-                        if self.include_subdomains {
-                            // For subdomains, try to reconstruct the full domain if possible
-                            if let (Some(subdomain), Some(domain), Some(suffix)) = (&tld_components.subdomain, &tld_components.domain, &tld_components.suffix) {
-                                domains.insert(format!("{}.{}.{}", subdomain, domain, suffix));
-                            } else if let (Some(domain), Some(suffix)) = (&tld_components.domain, &tld_components.suffix) {
-                                domains.insert(format!("{}.{}", domain, suffix));
-                            }
-                        } else {
-                            // Extract just the registrable domain
-                            if let (Some(domain), Some(suffix)) = (&tld_components.domain, &tld_components.suffix) {
-                                domains.insert(format!("{}.{}", domain, suffix));
-                            }
-                        }
-                    }
+                if self.options.strip_inline_comments {
+                    entry = strip_inline_comment(entry, prefix).trim_end();
                 }
             }
-            
-            // Extract domains from path segments that look like domains
-            for segment in &analysis.url_components.path_segments {
-                if segment.contains('.') && !segment.starts_with('%') {
-                    // This might be a domain in the path
-                    if self.include_subdomains {
-                        domains.insert(segment.clone());
-                    } else {
-                        let analyzer = TldAnalyzer::new();
-                        if let Ok(tld_components) = analyzer.extract(segment) {
-                            if let (Some(domain), Some(suffix)) = (tld_components.domain, tld_components.suffix) {
-                                domains.insert(format!("{}.{}", domain, suffix));
-                            }
-                        }
-                    }
-                }
+            if entry.is_empty() && self.options.skip_blank {
+                continue;
             }
+
+            return Some(Ok(entry.to_string()));
         }
-        
-        let mut sorted_domains: Vec<_> = domains.into_iter().collect();
-        sorted_domains.sort();
-        Ok(sorted_domains.join("\n"))
     }
 }
 
-impl OutputFormatter<UrlAnalysis> for WhoisFormatter {
-    type Error = std::fmt::Error;
+/// Reads one URL per line without loading the whole list, skipping comments and blank
+/// lines as configured by `options`
+pub fn read_url_lines<R: std::io::BufRead>(reader: R, options: ReaderOptions) -> UrlLineReader<R> {
+    UrlLineReader { lines: reader.lines(), options }
+}
+
+/// Lazily analyzes one URL per line of `reader` with a single analyzer, trimming each line
+/// and skipping blank ones; only the current line is held in memory. A line that fails to
+/// analyze, or is not valid UTF-8, yields an `Err` item and the stream carries on. Any other
+/// read error is yielded as `UrlAnalysisError::Io` and ends the stream.
+pub fn analyze_reader<R: std::io::BufRead>(reader: R) -> impl Iterator<Item = Result<UrlAnalysis, UrlAnalysisError>> {
+    let analyzer = ComprehensiveUrlAnalyzer::new();
+    let options = ReaderOptions { comment_prefix: None, ..ReaderOptions::default() };
     
-    fn format(&self, analysis: &UrlAnalysis) -> Result<String, Self::Error> {
-        self.format(&vec![analysis.clone()])
+    read_url_lines(reader, options).scan(false, move |failed, line| {
+        if *failed {
+            return None;
+        }
+        Some(match line {
+            Ok(url) => analyzer.analyze(&url),
+            Err(error) => {
+                *failed = error.kind() != std::io::ErrorKind::InvalidData;
+                Err(UrlAnalysisError::Io(error.to_string()))
+            }
+        })
+    })
+}
+
+/// Analyzes each URL in order, calling `callback` with its index and result as soon as it
+/// is ready, e.g. to drive a progress bar. Results are handed over rather than collected.
+pub fn analyze_urls_with_progress<F>(urls: &[&str], mut callback: F)
+where
+    F: FnMut(usize, &Result<UrlAnalysis, UrlAnalysisError>),
+{
+    let analyzer = ComprehensiveUrlAnalyzer::new();
+    for (index, url) in urls.iter().enumerate() {
+        callback(index, &analyzer.analyze(url));
     }
 }
 
-// ===== UTILITY FUNCTIONS =====
+/// One link from an HTTP `Link` header, as returned by `analyze_link_header`
+#[derive(Debug)]
+pub struct LinkHeaderEntry {
+    /// The target between `<` and `>`
+    pub url: String,
+    /// The `rel` parameter, unquoted (may hold several space-separated relations)
+    pub rel: Option<String>,
+    pub result: Result<UrlAnalysis, UrlAnalysisError>,
+}
 
-/// Convenience function to analyze a single URL
-pub fn analyze_url(url: &str) -> Result<UrlAnalysis, Box<dyn std::error::Error>> {
+/// Parses an RFC 8288 `Link` header (`<https://a.com/2>; rel="next", <...>; rel=prev`) and
+/// analyzes each target, keeping its `rel`. Commas inside `<...>` or quoted values don't split.
+pub fn analyze_link_header(header: &str) -> Vec<LinkHeaderEntry> {
     let analyzer = ComprehensiveUrlAnalyzer::new();
-    analyzer.analyze(url)
+    let mut entries = Vec::new();
+    let mut rest = header;
+
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>').map(|end| start + end) else {
+            break;
+        };
+        let url = rest[start + 1..end].trim().to_string();
+        rest = &rest[end + 1..];
+
+        // Parameters run to the next comma outside quotes
+        let mut in_quotes = false;
+        let params_end = rest.char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    in_quotes = !in_quotes;
+                }
+                c == ',' && !in_quotes
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let rel = rest[..params_end].split(';')
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("rel"))
+            .map(|(_, value)| value.trim().trim_matches('"').to_string());
+        rest = &rest[params_end..];
+
+        let result = analyzer.analyze(&url);
+        entries.push(LinkHeaderEntry { url, rel, result });
+    }
+
+    entries
 }
 
-/// Convenience function to analyze multiple URLs
-pub fn analyze_urls(urls: &[&str]) -> Vec<Result<UrlAnalysis, Box<dyn std::error::Error>>> {
-    let analyzer = ComprehensiveUrlAnalyzer::new();
-    urls.iter().map(|url| analyzer.analyze(url)).collect()
+/// Analyzes every `<loc>` in a sitemap (or sitemap index) document, in document order.
+/// XML entities and CDATA wrappers are unescaped first.
+pub fn analyze_sitemap_xml(xml: &str) -> Vec<Result<UrlAnalysis, UrlAnalysisError>> {
+    let mut locations = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = rest.find("<loc>") {
+        let after = &rest[start + "<loc>".len()..];
+        let Some(end) = after.find("</loc>") else {
+            break;
+        };
+        let raw = after[..end].trim();
+        let raw = raw.strip_prefix("<![CDATA[").and_then(|inner| inner.strip_suffix("]]>")).unwrap_or(raw);
+        locations.push(unescape_xml(raw.trim()));
+        rest = &after[end..];
+    }
+
+    let locations: Vec<&str> = locations.iter().map(|location| location.as_str()).collect();
+    analyze_urls(&locations)
 }
 
 // ===== TESTS =====
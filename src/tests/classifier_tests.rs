@@ -0,0 +1,135 @@
+use crate::{analyze_url, BeaconClassifier, BeaconReason, KnownSitePatterns, OrgMatcher, SiteData, BrandGuard, levenshtein_distance};
+
+#[test]
+fn test_beacon_classifier_flags_analytics_collect() -> Result<(), Box<dyn std::error::Error>> {
+    let analysis = analyze_url("https://www.google-analytics.com/collect?v=1&t=pageview&tid=UA-12345-1&cid=555")?;
+    
+    let verdict = BeaconClassifier::new().classify(&analysis);
+    
+    assert!(verdict.is_beacon);
+    assert!(verdict.reasons.contains(&BeaconReason::KnownDomain("google-analytics.com".to_string())));
+    assert!(verdict.reasons.contains(&BeaconReason::ParamSignature("t=pageview".to_string())));
+    assert!(verdict.reasons.contains(&BeaconReason::PixelPath("/collect".to_string())));
+    
+    Ok(())
+}
+
+#[test]
+fn test_beacon_classifier_ignores_regular_pages() -> Result<(), Box<dyn std::error::Error>> {
+    let analysis = analyze_url("https://example.com/blog/post?page=2")?;
+    
+    let verdict = BeaconClassifier::new().classify(&analysis);
+    
+    assert!(!verdict.is_beacon);
+    assert!(verdict.reasons.is_empty());
+    
+    Ok(())
+}
+
+#[test]
+fn test_beacon_classifier_custom_rules() -> Result<(), Box<dyn std::error::Error>> {
+    let analysis = analyze_url("https://metrics.internal.example/hit.gif?evt=view")?;
+    
+    assert!(!BeaconClassifier::new().classify(&analysis).is_beacon);
+    
+    let classifier = BeaconClassifier::empty()
+        .with_pixel_path("/hit.gif")
+        .with_param("evt", Some("view"));
+    let verdict = classifier.classify(&analysis);
+    
+    assert!(verdict.is_beacon);
+    assert_eq!(verdict.reasons.len(), 2);
+    
+    Ok(())
+}
+
+#[test]
+fn test_known_site_patterns_builtin_extractors() -> Result<(), Box<dyn std::error::Error>> {
+    let patterns = KnownSitePatterns::new();
+    
+    let video = patterns.extract(&analyze_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42s")?);
+    assert_eq!(video, Some(SiteData::YouTubeVideo { id: "dQw4w9WgXcQ".to_string() }));
+    assert_eq!(patterns.extract(&analyze_url("https://youtu.be/dQw4w9WgXcQ")?), video);
+    
+    let repo = patterns.extract(&analyze_url("https://github.com/rust-lang/rust.git")?);
+    assert_eq!(repo, Some(SiteData::GitHubRepo { owner: "rust-lang".to_string(), repo: "rust".to_string() }));
+    
+    assert_eq!(patterns.extract(&analyze_url("https://github.com/settings/profile")?), None);
+    assert_eq!(patterns.extract(&analyze_url("https://example.com/watch?v=dQw4w9WgXcQ")?), None);
+    
+    Ok(())
+}
+
+#[test]
+fn test_known_site_patterns_custom_extractor() -> Result<(), Box<dyn std::error::Error>> {
+    let patterns = KnownSitePatterns::empty().with_extractor("npm", |analysis| {
+        let is_npm = analysis.url_components.host.as_deref() == Some("www.npmjs.com");
+        match analysis.url_components.path_segments.as_slice() {
+            [package, name] if is_npm && package == "package" => Some(SiteData::Custom {
+                site: "npm".to_string(),
+                fields: vec![("package".to_string(), name.clone())],
+            }),
+            _ => None,
+        }
+    });
+    
+    let data = patterns.extract(&analyze_url("https://www.npmjs.com/package/left-pad")?);
+    assert_eq!(data, Some(SiteData::Custom { site: "npm".to_string(), fields: vec![("package".to_string(), "left-pad".to_string())] }));
+    // Built-ins are not included in an empty registry
+    assert_eq!(patterns.extract(&analyze_url("https://github.com/rust-lang/rust")?), None);
+    
+    Ok(())
+}
+
+#[test]
+fn test_org_matcher_groups_registrable_domains() -> Result<(), Box<dyn std::error::Error>> {
+    let orgs = OrgMatcher::new()
+        .with_group(&["google.com", "youtube.com", "googleapis.com"])
+        .with_group(&["example.com", "example.co.uk"]);
+    
+    let search = analyze_url("https://www.google.com/search?q=x")?;
+    let video = analyze_url("https://m.youtube.com/watch?v=dQw4w9WgXcQ")?;
+    let other = analyze_url("https://www.example.co.uk/")?;
+    
+    assert!(orgs.same_org(&search, &video));
+    assert!(!orgs.same_org(&search, &other));
+    assert!(orgs.same_org(&other, &analyze_url("https://shop.example.com/")?));
+    
+    // Nothing is grouped by default, but the same site always matches
+    assert!(!OrgMatcher::new().same_org(&search, &video));
+    assert!(OrgMatcher::new().same_org(&search, &analyze_url("https://mail.google.com/")?));
+    
+    Ok(())
+}
+
+#[test]
+fn test_brand_guard_matches_linear_scan() -> Result<(), Box<dyn std::error::Error>> {
+    let brands = [
+        "paypal.com", "google.com", "apple.com", "amazon.com", "microsoft.com", "netflix.com",
+        "github.com", "gitlab.com", "facebook.com", "instagram.com", "chase.com", "wellsfargo.com",
+        "PayPal.com", "dropbox.com", "adobe.com", "yahoo.com",
+    ];
+    let guard = BrandGuard::new(&brands);
+    assert_eq!(guard.len(), 15);
+    
+    assert_eq!(guard.nearest("paypa1.com", 1), vec![("paypal.com".to_string(), 1)]);
+    assert_eq!(guard.nearest("GOOGLE.COM.", 0), vec![("google.com".to_string(), 0)]);
+    
+    let lowered: Vec<String> = brands.iter().map(|b| b.to_ascii_lowercase()).collect();
+    for candidate in ["gitlub.com", "app1e.com", "amazon.co", "yah00.com", "unrelated.org", "chase.co"] {
+        for max_distance in 0..=3 {
+            let mut expected: Vec<(String, u32)> = lowered.iter()
+                .map(|brand| (brand.clone(), levenshtein_distance(candidate, brand)))
+                .filter(|(_, distance)| *distance <= max_distance)
+                .collect();
+            expected.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+            expected.dedup();
+            assert_eq!(guard.nearest(candidate, max_distance), expected, "{} within {}", candidate, max_distance);
+        }
+    }
+    
+    assert!(BrandGuard::new(&[]).nearest("paypal.com", 2).is_empty());
+    assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+    
+    Ok(())
+}
@@ -10,4 +10,6 @@ pub mod tld_analyzer_tests;
 pub mod formatter_tests;
 
 #[cfg(test)]
-pub mod integration_tests; 
\ No newline at end of file
+pub mod integration_tests; 
+#[cfg(test)]
+pub mod classifier_tests;
@@ -1,4 +1,4 @@
-use crate::TldAnalyzer;
+use crate::{TldAnalyzer, TldAnalyzerBuilder, TldOption, TldStatus, DomainLevels, suffix_list_diff};
 
 #[test]
 fn test_tld_analyzer_separately() -> Result<(), Box<dyn std::error::Error>> {
@@ -65,4 +65,215 @@ fn test_tld_analyzer_various_tlds() -> Result<(), Box<dyn std::error::Error>> {
     assert_eq!(components.suffix, Some("edu".to_string()));
     
     Ok(())
-} 
\ No newline at end of file
+} 
+#[test]
+fn test_tld_analyzer_offline_mode() -> Result<(), Box<dyn std::error::Error>> {
+    let tld_analyzer = TldAnalyzer::offline()?;
+    let components = tld_analyzer.extract("subdomain.domain.co.uk")?;
+    
+    assert_eq!(components.subdomain, Some("subdomain".to_string()));
+    assert_eq!(components.domain, Some("domain".to_string()));
+    assert_eq!(components.suffix, Some("co.uk".to_string()));
+    
+    // Only the ICANN snapshot is consulted
+    assert_eq!(tld_analyzer.extract("user.blogspot.com")?.registrable_domain(), Some("blogspot.com".to_string()));
+    
+    Ok(())
+}
+
+#[test]
+fn test_tld_analyzer_is_apex() -> Result<(), Box<dyn std::error::Error>> {
+    let tld_analyzer = TldAnalyzer::new();
+    
+    assert!(tld_analyzer.extract("example.com")?.is_apex());
+    assert!(tld_analyzer.extract("domain.co.uk")?.is_apex());
+    assert!(!tld_analyzer.extract("www.example.com")?.is_apex());
+    assert!(!tld_analyzer.extract("api.mail.example.org")?.is_apex());
+    
+    Ok(())
+}
+
+#[test]
+fn test_suffix_list_diff() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::temp_dir().join(format!("psl_diff_{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let old_path = dir.join("old.dat");
+    let new_path = dir.join("new.dat");
+    
+    std::fs::write(&old_path, "// ===BEGIN ICANN DOMAINS===\ncom\nuk\nco.uk\n*.ck\n!www.ck\n\n// retired\nxn--old\n")?;
+    std::fs::write(&new_path, "// ===BEGIN ICANN DOMAINS===\ncom\nuk\nco.uk\n*.ck\n\n// new gTLDs\nzip\nmov  some trailing text\n")?;
+    
+    let diff = suffix_list_diff(&old_path, &new_path)?;
+    std::fs::remove_dir_all(&dir)?;
+    
+    assert_eq!(diff.added, vec!["mov", "zip"]);
+    assert_eq!(diff.removed, vec!["xn--old"]);
+    assert!(diff.added_exceptions.is_empty());
+    assert_eq!(diff.removed_exceptions, vec!["www.ck"]);
+    
+    Ok(())
+}
+
+#[test]
+fn test_tld_analyzer_internal_suffixes() -> Result<(), Box<dyn std::error::Error>> {
+    let tld_analyzer = TldAnalyzer::new();
+    let components = tld_analyzer.extract("service.namespace.svc.cluster.local")?;
+    
+    assert_eq!(components.suffix, Some("cluster.local".to_string()));
+    assert_eq!(components.domain, Some("svc".to_string()));
+    assert_eq!(components.subdomain, Some("service.namespace".to_string()));
+    assert_eq!(components.status, TldStatus::Internal);
+    
+    // Public hosts are unaffected
+    assert_eq!(tld_analyzer.extract("example.com")?.status, TldStatus::Public);
+    
+    // Custom internal suffixes can be added
+    let custom = TldAnalyzer::new().with_internal_suffix("corp");
+    let components = custom.extract("build.eng.corp")?;
+    assert_eq!(components.suffix, Some("corp".to_string()));
+    assert_eq!(components.domain, Some("eng".to_string()));
+    assert_eq!(components.status, TldStatus::Internal);
+    
+    Ok(())
+}
+
+#[test]
+fn test_tld_analyzer_icann_only_vs_private() -> Result<(), Box<dyn std::error::Error>> {
+    let icann = TldAnalyzer::new().with_icann_only(true).extract("user.github.io")?;
+    assert_eq!(icann.suffix, Some("io".to_string()));
+    assert_eq!(icann.domain, Some("github".to_string()));
+    assert_eq!(icann.subdomain, Some("user".to_string()));
+    assert_eq!(icann.registrable_domain(), Some("github.io".to_string()));
+    
+    let private = TldAnalyzer::new().with_icann_only(false).extract("user.github.io")?;
+    assert_eq!(private.suffix, Some("github.io".to_string()));
+    assert_eq!(private.domain, Some("user".to_string()));
+    assert_eq!(private.subdomain, None);
+    assert_eq!(private.status, TldStatus::Public);
+    assert_eq!(private.registrable_domain(), Some("user.github.io".to_string()));
+    
+    // ICANN-only is the default; hosts outside the PRIVATE overlay are unaffected
+    assert_eq!(TldAnalyzer::new().extract("user.github.io")?.suffix, Some("io".to_string()));
+    let unlisted = TldAnalyzer::new().with_icann_only(false).extract("docs.example.co.uk")?;
+    assert_eq!(unlisted.suffix, Some("co.uk".to_string()));
+    
+    // The overlay can be extended
+    let custom = TldAnalyzer::new().with_icann_only(false).with_private_suffix("pages.example.com");
+    assert_eq!(custom.extract("team.pages.example.com")?.registrable_domain(), Some("team.pages.example.com".to_string()));
+    
+    Ok(())
+}
+
+#[test]
+fn test_tld_analyzer_timeout() -> Result<(), Box<dyn std::error::Error>> {
+    use std::time::Duration;
+    
+    let slow = TldAnalyzer::new()
+        .with_timeout(Duration::from_millis(20))
+        .with_artificial_delay(Duration::from_millis(500));
+    let components = slow.extract("example.com")?;
+    assert_eq!(components.status, TldStatus::TimedOut);
+    assert_eq!(components.domain, None);
+    
+    // Fast extractions complete normally under a timeout
+    let fast = TldAnalyzer::new().with_timeout(Duration::from_secs(5));
+    let components = fast.extract("sub.example.com")?;
+    assert_eq!(components.status, TldStatus::Public);
+    assert_eq!(components.domain, Some("example".to_string()));
+    
+    Ok(())
+}
+
+#[test]
+fn test_tld_analyzer_domain_levels() -> Result<(), Box<dyn std::error::Error>> {
+    let tld_analyzer = TldAnalyzer::new();
+    
+    let levels = tld_analyzer.extract("api.mail.example.co.uk")?.domain_levels();
+    assert_eq!(levels, DomainLevels {
+        etld: Some("co.uk".to_string()),
+        etld_plus_1: Some("example.co.uk".to_string()),
+        etld_plus_2: Some("mail.example.co.uk".to_string()),
+    });
+    
+    let levels = tld_analyzer.extract("example.com")?.domain_levels();
+    assert_eq!(levels.etld_plus_1, Some("example.com".to_string()));
+    assert_eq!(levels.etld_plus_2, None);
+    
+    Ok(())
+}
+
+#[test]
+fn test_tld_analyzer_subdomain_contains_domain_like() -> Result<(), Box<dyn std::error::Error>> {
+    let tld_analyzer = TldAnalyzer::new();
+    
+    let components = tld_analyzer.extract("paypal.com.evil.ru")?;
+    assert_eq!(components.registrable_domain(), Some("evil.ru".to_string()));
+    assert_eq!(components.subdomain_contains_domain_like(&tld_analyzer), vec!["paypal.com".to_string()]);
+    
+    let components = tld_analyzer.extract("secure.mybank.co.uk.account-verify.com")?;
+    assert_eq!(components.subdomain_contains_domain_like(&tld_analyzer), vec!["mybank.co.uk".to_string()]);
+    
+    let components = tld_analyzer.extract("mail.internal.example.com")?;
+    assert!(components.subdomain_contains_domain_like(&tld_analyzer).is_empty());
+    
+    // Service and environment labels that happen to precede a TLD are not brands
+    for host in ["api.dev.example.com", "shop.app.example.com", "www.staging.api.dev.example.com", "mail.ru.example.org"] {
+        let components = tld_analyzer.extract(host)?;
+        assert!(components.subdomain_contains_domain_like(&tld_analyzer).is_empty(), "{}", host);
+    }
+    let components = tld_analyzer.extract("api.paypal.com.evil.ru")?;
+    assert_eq!(components.subdomain_contains_domain_like(&tld_analyzer), vec!["paypal.com".to_string()]);
+    
+    Ok(())
+}
+
+#[test]
+fn test_tld_analyzer_numeric_suffix() -> Result<(), Box<dyn std::error::Error>> {
+    let analyzer = TldAnalyzer::new();
+    
+    let result = analyzer.extract("example.123")?;
+    assert_eq!(result.status, TldStatus::NumericSuffix);
+    assert_eq!(result.registrable_domain(), None);
+    
+    // Real IPv4 addresses, including shorthand forms, are not flagged
+    assert_ne!(analyzer.extract("192.168.1.1")?.status, TldStatus::NumericSuffix);
+    assert_ne!(analyzer.extract("127.1")?.status, TldStatus::NumericSuffix);
+    assert_ne!(analyzer.extract("example.com")?.status, TldStatus::NumericSuffix);
+    
+    Ok(())
+}
+
+#[test]
+fn test_tld_analyzer_builder_and_options() -> Result<(), Box<dyn std::error::Error>> {
+    // The bundled snapshot has no PRIVATE-section entries
+    let default = TldAnalyzerBuilder::new().build();
+    assert_eq!(default.extract("user.blogspot.com")?.registrable_domain(), Some("blogspot.com".to_string()));
+    
+    // A cache file is used as written, so it can carry extra suffixes
+    let cache = std::env::temp_dir().join(format!("tld_cache_{}.json", std::process::id()));
+    std::fs::write(&cache, r#"["com", "co.uk", "blogspot.com"]"#)?;
+    let custom = TldAnalyzer::builder().with_cache_path(&cache).build();
+    assert_eq!(custom.extract("user.blogspot.com")?.registrable_domain(), Some("user.blogspot.com".to_string()));
+    assert_eq!(custom.extract("a.b.co.uk")?.registrable_domain(), Some("b.co.uk".to_string()));
+    
+    let raw = TldAnalyzer::with_options(TldOption::default().cache_path(&cache.to_string_lossy()));
+    assert_eq!(raw.extract("site.blogspot.com")?.suffix, Some("blogspot.com".to_string()));
+    std::fs::remove_file(&cache)?;
+    
+    // Private domains layer the PRIVATE-section overlay on the snapshot
+    let private = TldAnalyzer::builder().with_private_domains(true).build();
+    assert_eq!(private.extract("user.blogspot.com")?.registrable_domain(), Some("user.blogspot.com".to_string()));
+    assert_eq!(private.extract("www.example.co.uk")?.registrable_domain(), Some("example.co.uk".to_string()));
+    let icann = TldAnalyzer::builder().with_private_domains(false).build();
+    assert_eq!(icann.extract("user.blogspot.com")?.registrable_domain(), Some("blogspot.com".to_string()));
+    
+    // Writing back seeds a missing cache file from the snapshot
+    let seeded = std::env::temp_dir().join(format!("tld_cache_seeded_{}.json", std::process::id()));
+    let analyzer = TldAnalyzer::builder().with_cache_path(&seeded).with_cache_write_back(true).build();
+    assert_eq!(analyzer.extract("www.example.co.uk")?.registrable_domain(), Some("example.co.uk".to_string()));
+    let written: Vec<String> = serde_json::from_str(&std::fs::read_to_string(&seeded)?)?;
+    assert!(written.iter().any(|suffix| suffix == "co.uk"));
+    std::fs::remove_file(&seeded)?;
+    
+    Ok(())
+}